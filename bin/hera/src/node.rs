@@ -1,7 +1,11 @@
 //! Node subcommand for Hera.
 
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use alloy_primitives::Address;
 use clap::Args;
-use eyre::{bail, Result};
+use eyre::{bail, eyre, Result};
+use op_net::driver::NetworkDriver;
 use rollup::{Driver, HeraArgsExt};
 use tracing::info;
 
@@ -14,6 +18,26 @@ pub struct NodeCommand {
     /// The Hera Rollup node configuration.
     #[clap(flatten)]
     pub hera_config: HeraArgsExt,
+    /// Run the p2p network stack and follow unsafe blocks gossiped by the sequencer.
+    #[clap(
+        long = "hera.network",
+        requires = "unsafe_block_signer",
+        help = "Follow unsafe blocks gossiped on the p2p network"
+    )]
+    pub network: bool,
+    /// Port to listen for gossip on.
+    #[clap(
+        long = "hera.gossip-port",
+        default_value = "9099",
+        help = "Port to listen for gossip on"
+    )]
+    pub gossip_port: u16,
+    /// Address of the sequencer's p2p key, used to verify the signature of gossiped blocks.
+    #[clap(
+        long = "hera.unsafe-block-signer",
+        help = "Address that signs the unsafe blocks gossiped by the sequencer"
+    )]
+    pub unsafe_block_signer: Option<Address>,
 }
 
 impl NodeCommand {
@@ -25,7 +49,22 @@ impl NodeCommand {
         );
 
         let cfg = self.hera_config.get_l2_config()?;
-        let driver = Driver::standalone(self.hera_config, cfg).await?;
+        let mut driver = Driver::standalone(self.hera_config, cfg.clone()).await?;
+
+        if self.network {
+            let signer = self.unsafe_block_signer.ok_or(eyre!("Missing unsafe block signer"))?;
+            let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), self.gossip_port);
+            let mut network = NetworkDriver::builder()
+                .with_chain_id(cfg.l2_chain_id)
+                .with_unsafe_block_signer(signer)
+                .with_gossip_addr(socket)
+                .build()?;
+            let recv = network.take_unsafe_block_recv().ok_or(eyre!("No unsafe block receiver"))?;
+            network.start()?;
+            info!("Following unsafe blocks on the p2p network, gossip port: {}", self.gossip_port);
+
+            driver = driver.with_unsafe_block_recv(recv);
+        }
 
         if let Err(e) = driver.start().await {
             bail!("[CRIT] Rollup driver failed: {:?}", e)
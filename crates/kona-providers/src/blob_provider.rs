@@ -1,31 +1,142 @@
 use alloc::{collections::VecDeque, sync::Arc};
 use hashbrown::HashMap;
 
-use alloy::{eips::eip4844::Blob, primitives::B256};
+use alloy::{
+    consensus::BlobTransactionSidecar as AlloyBlobTransactionSidecar,
+    eips::eip4844::{env_settings::EnvKzgSettings, Blob, BYTES_PER_BLOB},
+    primitives::B256,
+};
 use async_trait::async_trait;
 use eyre::{eyre, Result};
 use kona_derive::{
     errors::BlobProviderError,
-    online::{
-        OnlineBeaconClient, OnlineBlobProviderBuilder, OnlineBlobProviderWithFallback,
-        SimpleSlotDerivation,
-    },
+    online::{OnlineBeaconClient, OnlineBlobProvider, SimpleSlotDerivation, SlotDerivation},
     traits::BlobProvider,
 };
-use kona_primitives::IndexedBlobHash;
+use kona_primitives::{BlobSidecar, IndexedBlobHash};
+use metrics::{counter, gauge};
 use op_alloy_protocol::BlockInfo;
 use parking_lot::Mutex;
 use reth::primitives::BlobTransactionSidecar;
 use tracing::warn;
 use url::Url;
 
+/// An online blob source, reading blob sidecars from the beacon API of the given URL.
+type BeaconBlobSource = OnlineBlobProvider<OnlineBeaconClient, SimpleSlotDerivation>;
+
 /// A blob provider that first attempts to fetch blobs from a primary beacon client and
 /// falls back to a secondary blob archiver if the primary fails.
 ///
 /// Any blob archiver just needs to implement the beacon
 /// [`blob_sidecars` API](https://ethereum.github.io/beacon-APIs/#/Beacon/getBlobSidecars)
-pub type DurableBlobProvider =
-    OnlineBlobProviderWithFallback<OnlineBeaconClient, OnlineBeaconClient, SimpleSlotDerivation>;
+///
+/// Blobs are checked against their KZG commitment and proof, and commitments against the
+/// requested versioned hashes. Invalid blobs from a source trigger the fallback to the next one.
+#[derive(Debug, Clone)]
+pub struct DurableBlobProvider {
+    /// The beacon node, also used to derive the slot of L1 blocks.
+    primary: BeaconBlobSource,
+    /// The blob archiver, if any.
+    fallback: Option<BeaconBlobSource>,
+}
+
+impl DurableBlobProvider {
+    /// Creates a new [DurableBlobProvider] with a primary beacon client
+    /// and an optional fallback blob archiver.
+    pub fn new(beacon_client_url: Url, blob_archiver_url: Option<Url>) -> Self {
+        Self {
+            primary: beacon_blob_source(&beacon_client_url),
+            fallback: blob_archiver_url.as_ref().map(beacon_blob_source),
+        }
+    }
+
+    /// Returns the beacon slot of the given L1 block timestamp.
+    async fn slot(&mut self, timestamp: u64) -> Result<u64, BlobProviderError> {
+        self.primary.load_configs().await?;
+        let genesis_time = self.primary.genesis_time.ok_or(BlobProviderError::SlotDerivation)?;
+        let slot_interval = self.primary.slot_interval.ok_or(BlobProviderError::SlotDerivation)?;
+        SimpleSlotDerivation::slot(genesis_time, slot_interval, timestamp)
+    }
+}
+
+/// Creates a [BeaconBlobSource] for the beacon API at the given URL.
+fn beacon_blob_source(url: &Url) -> BeaconBlobSource {
+    let client = OnlineBeaconClient::new_http(url.as_str().trim_end_matches('/').to_string());
+    OnlineBlobProvider::new(client, None, None)
+}
+
+#[async_trait]
+impl BlobProvider for DurableBlobProvider {
+    type Error = BlobProviderError;
+
+    /// Fetches and verifies blobs for a given block ref and the blob hashes.
+    async fn get_blobs(
+        &mut self,
+        block_ref: &BlockInfo,
+        blob_hashes: &[IndexedBlobHash],
+    ) -> Result<Vec<Blob>, BlobProviderError> {
+        if blob_hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let slot = self.slot(block_ref.timestamp).await.inspect_err(|e| {
+            warn!("Failed to derive the slot of block {}: {:?}", block_ref.hash, e);
+        })?;
+
+        let sources = core::iter::once(("primary", &self.primary))
+            .chain(self.fallback.iter().map(|source| ("fallback", source)));
+        let mut last_error = None;
+        for (source, provider) in sources {
+            let sidecars = match provider.fetch_sidecars(slot, blob_hashes).await {
+                Ok(sidecars) => sidecars,
+                Err(e) => {
+                    warn!("Failed to fetch blob sidecars from {} source: {:?}", source, e);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            match verify_blobs(&sidecars, blob_hashes) {
+                Ok(blobs) => return Ok(blobs),
+                Err(e) => {
+                    counter!("hera_blob_verification_failures_total", "source" => source)
+                        .increment(1);
+                    warn!(
+                        "Invalid blobs from {} source for block {}: {:?}",
+                        source, block_ref.hash, e
+                    );
+                    last_error = Some(BlobProviderError::Backend(e.to_string()));
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| BlobProviderError::Backend("No blob source".into())))
+    }
+}
+
+/// Verifies the blobs of the given sidecars and returns them in the order of the
+/// requested hashes.
+///
+/// Each blob is checked against its KZG commitment and proof, and each commitment
+/// against the requested versioned hash.
+fn verify_blobs(sidecars: &[BlobSidecar], blob_hashes: &[IndexedBlobHash]) -> Result<Vec<Blob>> {
+    let mut requested = AlloyBlobTransactionSidecar::default();
+    for hash in blob_hashes {
+        let sidecar = sidecars
+            .iter()
+            .find(|sidecar| sidecar.index == hash.index as u64)
+            .ok_or_else(|| eyre!("Missing blob sidecar at index {}", hash.index))?;
+        requested.blobs.push(sidecar.blob);
+        requested.commitments.push(sidecar.kzg_commitment);
+        requested.proofs.push(sidecar.kzg_proof);
+    }
+
+    let versioned_hashes = blob_hashes.iter().map(|h| h.hash).collect::<Vec<_>>();
+    requested
+        .validate(&versioned_hashes, EnvKzgSettings::Default.get())
+        .map_err(|e| eyre!("Blob verification failed: {}", e))?;
+    Ok(requested.blobs)
+}
 
 /// Layered [BlobProvider] for the Kona derivation pipeline.
 ///
@@ -35,7 +146,7 @@ pub type DurableBlobProvider =
 /// - If the blobs are still not found, it tries to fetch them from a blob archiver (if set).
 /// - If all sources fail, the provider will return a [BlobProviderError].
 #[derive(Debug, Clone)]
-pub struct LayeredBlobProvider {
+pub struct LayeredBlobProvider<B = DurableBlobProvider> {
     /// In-memory inner blob provider, used for locally caching blobs as
     /// they come during live sync (when following the chain tip).
     memory: Arc<Mutex<InnerBlobProvider>>,
@@ -46,18 +157,24 @@ pub struct LayeredBlobProvider {
     ///
     /// The `Durable` setup allows to specify two different
     /// endpoints for a primary and a fallback blob provider.
-    online: DurableBlobProvider,
+    online: B,
 }
 
 /// A blob provider that hold blobs in memory.
+///
+/// Blobs are cached by block, up to `capacity` blocks. When the capacity is reached,
+/// the least recently used block is evicted along with its blobs.
 #[derive(Debug)]
 pub struct InnerBlobProvider {
-    /// Maximum number of blobs to keep in memory.
+    /// Maximum number of blocks to keep blobs for in memory.
     capacity: usize,
-    /// Order of key insertion for oldest entry eviction.
+    /// Order of block usage, from least to most recently used, for eviction.
     key_order: VecDeque<B256>,
-    /// Maps block hashes to blob hashes to blob sidecars.
-    blocks_to_blob_sidecars: HashMap<B256, Vec<BlobTransactionSidecar>>,
+    /// Maps block hashes to the versioned hashes of their blobs.
+    blocks_to_blob_hashes: HashMap<B256, Vec<B256>>,
+    /// Maps versioned hashes to blobs, along with the number of cached blocks that
+    /// contain them. The same blob can be part of several blocks, e.g. after a reorg.
+    blobs: HashMap<B256, (Box<Blob>, usize)>,
 }
 
 impl InnerBlobProvider {
@@ -65,42 +182,106 @@ impl InnerBlobProvider {
     pub fn with_capacity(cap: usize) -> Self {
         Self {
             capacity: cap,
-            blocks_to_blob_sidecars: HashMap::with_capacity(cap),
             key_order: VecDeque::with_capacity(cap),
+            blocks_to_blob_hashes: HashMap::with_capacity(cap),
+            blobs: HashMap::new(),
         }
     }
 
-    /// Inserts multiple blob sidecars nto the provider.
+    /// Inserts multiple blob sidecars into the provider.
     pub fn insert_blob_sidecars(
         &mut self,
         block_hash: B256,
         sidecars: Vec<BlobTransactionSidecar>,
     ) {
-        if let Some(existing_blobs) = self.blocks_to_blob_sidecars.get_mut(&block_hash) {
-            existing_blobs.extend(sidecars);
-        } else {
-            if self.blocks_to_blob_sidecars.len() >= self.capacity {
-                if let Some(oldest) = self.key_order.pop_front() {
-                    self.blocks_to_blob_sidecars.remove(&oldest);
+        let blobs = sidecars
+            .iter()
+            .flat_map(|sidecar| sidecar.versioned_hashes().zip(sidecar.blobs.iter().copied()));
+        self.insert_blobs(block_hash, blobs);
+    }
+
+    /// Inserts blobs of the given block, keyed by their versioned hash, into the provider.
+    pub fn insert_blobs(
+        &mut self,
+        block_hash: B256,
+        blobs: impl IntoIterator<Item = (B256, Blob)>,
+    ) {
+        self.touch(block_hash);
+        let block_hashes = self.blocks_to_blob_hashes.entry(block_hash).or_default();
+
+        for (hash, blob) in blobs {
+            if block_hashes.contains(&hash) {
+                continue;
+            }
+            block_hashes.push(hash);
+            self.blobs.entry(hash).or_insert_with(|| (Box::new(blob), 0)).1 += 1;
+        }
+
+        while self.key_order.len() > self.capacity {
+            if let Some(oldest) = self.key_order.pop_front() {
+                self.remove_block(&oldest);
+            }
+        }
+        self.record_usage();
+    }
+
+    /// Returns the blobs with the given hashes, in the requested order.
+    /// Blobs that are not cached are returned as `None`.
+    pub fn get_blobs(
+        &mut self,
+        block_hash: &B256,
+        blob_hashes: &[IndexedBlobHash],
+    ) -> Vec<Option<Blob>> {
+        if self.blocks_to_blob_hashes.contains_key(block_hash) {
+            self.touch(*block_hash);
+        }
+
+        blob_hashes.iter().map(|h| self.blobs.get(&h.hash).map(|(blob, _)| **blob)).collect()
+    }
+
+    /// Marks the given block as the most recently used.
+    fn touch(&mut self, block_hash: B256) {
+        if let Some(pos) = self.key_order.iter().position(|h| *h == block_hash) {
+            self.key_order.remove(pos);
+        }
+        self.key_order.push_back(block_hash);
+    }
+
+    /// Removes a block and the blobs that are not part of any other cached block.
+    fn remove_block(&mut self, block_hash: &B256) {
+        for hash in self.blocks_to_blob_hashes.remove(block_hash).unwrap_or_default() {
+            if let Some((_, refs)) = self.blobs.get_mut(&hash) {
+                *refs -= 1;
+                if *refs == 0 {
+                    self.blobs.remove(&hash);
                 }
             }
-            self.blocks_to_blob_sidecars.insert(block_hash, sidecars);
         }
     }
+
+    /// Records the memory usage of the provider.
+    fn record_usage(&self) {
+        gauge!("hera_blob_provider_cached_blocks").set(self.blocks_to_blob_hashes.len() as f64);
+        gauge!("hera_blob_provider_cached_blobs").set(self.blobs.len() as f64);
+        gauge!("hera_blob_provider_memory_bytes").set((self.blobs.len() * BYTES_PER_BLOB) as f64);
+    }
 }
 
 impl LayeredBlobProvider {
     /// Creates a new [LayeredBlobProvider] with a local blob store, an online primary beacon
     /// client and an optional fallback blob archiver for fetching blobs.
     pub fn new(beacon_client_url: Url, blob_archiver_url: Option<Url>) -> Self {
-        let memory = Arc::new(Mutex::new(InnerBlobProvider::with_capacity(512)));
+        let online = DurableBlobProvider::new(beacon_client_url, blob_archiver_url);
 
-        let online = OnlineBlobProviderBuilder::new()
-            .with_primary(beacon_client_url.as_str().trim_end_matches('/').to_string())
-            .with_fallback(
-                blob_archiver_url.map(|url| url.as_str().trim_end_matches('/').to_string()),
-            )
-            .build();
+        Self::with_online_provider(online)
+    }
+}
+
+impl<B> LayeredBlobProvider<B> {
+    /// Creates a new [LayeredBlobProvider] with a local blob store and the given online
+    /// blob provider.
+    pub fn with_online_provider(online: B) -> Self {
+        let memory = Arc::new(Mutex::new(InnerBlobProvider::with_capacity(512)));
 
         Self { memory, online }
     }
@@ -116,35 +297,21 @@ impl LayeredBlobProvider {
     }
 
     /// Attempts to fetch blobs using the in-memory blob store.
+    /// Blobs that are not cached are returned as `None`.
     #[inline]
-    async fn memory_blob_load(
+    fn memory_blob_load(
         &mut self,
         block_ref: &BlockInfo,
         blob_hashes: &[IndexedBlobHash],
-    ) -> Result<Vec<Blob>> {
-        let locked = self.memory.lock();
-
-        let sidecars_for_block = locked
-            .blocks_to_blob_sidecars
-            .get(&block_ref.hash)
-            .ok_or(eyre!("No blob sidecars found for block ref: {:?}", block_ref))?;
-
-        // for each sidecar, get the blob hashes and check if any of them are
-        // part of the requested hashes. If they are, add the corresponding blobs
-        // to the blobs vector.
-        let mut blobs = Vec::with_capacity(blob_hashes.len());
-        let requested_hashes = blob_hashes.iter().map(|h| h.hash).collect::<Vec<_>>();
-        for sidecar in sidecars_for_block {
-            for (hash, blob) in sidecar.versioned_hashes().zip(&sidecar.blobs) {
-                if requested_hashes.contains(&hash) {
-                    blobs.push(*blob);
-                }
-            }
-        }
-
-        Ok(blobs)
+    ) -> Vec<Option<Blob>> {
+        self.memory.lock().get_blobs(&block_ref.hash, blob_hashes)
     }
+}
 
+impl<B> LayeredBlobProvider<B>
+where
+    B: BlobProvider<Error = BlobProviderError> + Send,
+{
     /// Attempts to fetch blobs using the online blob provider.
     #[inline]
     async fn online_blob_load(
@@ -157,20 +324,259 @@ impl LayeredBlobProvider {
 }
 
 #[async_trait]
-impl BlobProvider for LayeredBlobProvider {
+impl<B> BlobProvider for LayeredBlobProvider<B>
+where
+    B: BlobProvider<Error = BlobProviderError> + Send,
+{
     type Error = BlobProviderError;
 
     /// Fetches blobs for a given block ref and the blob hashes.
+    ///
+    /// Blobs are returned in the order of the requested hashes. Only the blobs missing
+    /// from memory are fetched from the online provider, and they are cached afterwards.
     async fn get_blobs(
         &mut self,
         block_ref: &BlockInfo,
         blob_hashes: &[IndexedBlobHash],
     ) -> Result<Vec<Blob>, BlobProviderError> {
-        if let Ok(b) = self.memory_blob_load(block_ref, blob_hashes).await {
-            return Ok(b);
-        } else {
-            warn!("Blob provider falling back to online provider");
-            self.online_blob_load(block_ref, blob_hashes).await
+        let mut blobs = self.memory_blob_load(block_ref, blob_hashes);
+
+        // The online provider returns blobs in the order of their index in the block.
+        let mut missing = blob_hashes
+            .iter()
+            .zip(&blobs)
+            .enumerate()
+            .filter(|(_, (_, blob))| blob.is_none())
+            .map(|(pos, (hash, _))| (pos, hash.clone()))
+            .collect::<Vec<_>>();
+        missing.sort_by_key(|(_, hash)| hash.index);
+
+        let result = match missing.len() {
+            0 => "hit",
+            n if n == blob_hashes.len() => "miss",
+            _ => "partial",
+        };
+        counter!("hera_blob_provider_lookups_total", "result" => result).increment(1);
+
+        if !missing.is_empty() {
+            warn!(
+                "Blob provider falling back to online provider for {} out of {} blobs",
+                missing.len(),
+                blob_hashes.len()
+            );
+            let missing_hashes = missing.iter().map(|(_, hash)| hash.clone()).collect::<Vec<_>>();
+            let fetched = self.online_blob_load(block_ref, &missing_hashes).await?;
+            if fetched.len() != missing.len() {
+                return Err(BlobProviderError::SidecarLengthMismatch(missing.len(), fetched.len()));
+            }
+
+            self.memory.lock().insert_blobs(
+                block_ref.hash,
+                missing.iter().zip(&fetched).map(|((_, hash), blob)| (hash.hash, *blob)),
+            );
+            for ((pos, _), blob) in missing.into_iter().zip(fetched) {
+                blobs[pos] = Some(blob);
+            }
         }
+
+        Ok(blobs.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::eips::eip4844::{kzg_to_versioned_hash, Bytes48, VERSIONED_HASH_VERSION_KZG};
+
+    /// An online blob provider serving the blobs of a sidecar, and recording the requests.
+    #[derive(Debug, Default)]
+    struct StubBlobProvider {
+        blobs: HashMap<B256, Blob>,
+        requests: Vec<Vec<IndexedBlobHash>>,
+    }
+
+    #[async_trait]
+    impl BlobProvider for StubBlobProvider {
+        type Error = BlobProviderError;
+
+        async fn get_blobs(
+            &mut self,
+            _block_ref: &BlockInfo,
+            blob_hashes: &[IndexedBlobHash],
+        ) -> Result<Vec<Blob>, BlobProviderError> {
+            self.requests.push(blob_hashes.to_vec());
+            blob_hashes
+                .iter()
+                .map(|h| self.blobs.get(&h.hash).copied())
+                .collect::<Option<_>>()
+                .ok_or(BlobProviderError::Backend("Blob not found".into()))
+        }
+    }
+
+    /// Returns a sidecar with one blob per given byte, each with a distinct commitment.
+    fn sidecar(bytes: &[u8]) -> BlobTransactionSidecar {
+        BlobTransactionSidecar {
+            blobs: bytes.iter().map(|b| Blob::repeat_byte(*b)).collect(),
+            commitments: bytes.iter().map(|b| Bytes48::repeat_byte(*b)).collect(),
+            proofs: bytes.iter().map(|_| Bytes48::ZERO).collect(),
+        }
+    }
+
+    fn indexed_hashes(sidecar: &BlobTransactionSidecar) -> Vec<IndexedBlobHash> {
+        sidecar
+            .versioned_hashes()
+            .enumerate()
+            .map(|(index, hash)| IndexedBlobHash { index, hash })
+            .collect()
+    }
+
+    #[test]
+    fn test_get_blobs_by_versioned_hash() {
+        let mut provider = InnerBlobProvider::with_capacity(4);
+        let block = B256::with_last_byte(1);
+        let blob_sidecar = sidecar(&[1, 2, 3]);
+        provider.insert_blob_sidecars(block, vec![blob_sidecar.clone()]);
+
+        let hashes = indexed_hashes(&blob_sidecar);
+        assert!(hashes.iter().all(|h| h.hash[0] == VERSIONED_HASH_VERSION_KZG));
+        let blobs = provider.get_blobs(&block, &[hashes[2].clone(), hashes[0].clone()]);
+        assert_eq!(blobs, vec![Some(Blob::repeat_byte(3)), Some(Blob::repeat_byte(1))]);
+
+        let missing = indexed_hashes(&sidecar(&[4])).remove(0);
+        let blobs = provider.get_blobs(&block, &[hashes[1].clone(), missing]);
+        assert_eq!(blobs, vec![Some(Blob::repeat_byte(2)), None]);
+    }
+
+    #[test]
+    fn test_memory_is_bounded() {
+        let mut provider = InnerBlobProvider::with_capacity(4);
+        for i in 0..100u8 {
+            provider.insert_blob_sidecars(B256::with_last_byte(i), vec![sidecar(&[i, i + 100])]);
+        }
+
+        assert_eq!(provider.key_order.len(), 4);
+        assert_eq!(provider.blocks_to_blob_hashes.len(), 4);
+        assert_eq!(provider.blobs.len(), 8);
+        assert!(provider.blocks_to_blob_hashes.contains_key(&B256::with_last_byte(96)));
+        assert!(!provider.blocks_to_blob_hashes.contains_key(&B256::with_last_byte(95)));
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut provider = InnerBlobProvider::with_capacity(2);
+        let (first, second, third) =
+            (B256::with_last_byte(1), B256::with_last_byte(2), B256::with_last_byte(3));
+        provider.insert_blob_sidecars(first, vec![sidecar(&[1])]);
+        provider.insert_blob_sidecars(second, vec![sidecar(&[2])]);
+
+        // Reading the first block makes the second one the least recently used
+        provider.get_blobs(&first, &indexed_hashes(&sidecar(&[1])));
+        provider.insert_blob_sidecars(third, vec![sidecar(&[3])]);
+
+        assert!(provider.blocks_to_blob_hashes.contains_key(&first));
+        assert!(!provider.blocks_to_blob_hashes.contains_key(&second));
+        assert_eq!(provider.blobs.len(), 2);
+    }
+
+    #[test]
+    fn test_shared_blobs_outlive_evicted_blocks() {
+        let mut provider = InnerBlobProvider::with_capacity(1);
+        let blob_sidecar = sidecar(&[1]);
+        let (first, second) = (B256::with_last_byte(1), B256::with_last_byte(2));
+        provider.insert_blob_sidecars(first, vec![blob_sidecar.clone()]);
+        provider.insert_blob_sidecars(first, vec![blob_sidecar.clone()]);
+        provider.insert_blob_sidecars(second, vec![blob_sidecar.clone()]);
+
+        assert_eq!(provider.blocks_to_blob_hashes.len(), 1);
+        assert_eq!(provider.get_blobs(&second, &indexed_hashes(&blob_sidecar)).len(), 1);
+        assert!(provider.get_blobs(&second, &indexed_hashes(&blob_sidecar))[0].is_some());
+    }
+
+    #[test]
+    fn test_insert_fetched_blobs() {
+        let mut provider = InnerBlobProvider::with_capacity(2);
+        let block = B256::with_last_byte(1);
+        let hashes = indexed_hashes(&sidecar(&[1, 2]));
+        provider.insert_blob_sidecars(block, vec![sidecar(&[1])]);
+        assert_eq!(provider.get_blobs(&block, &hashes), vec![Some(Blob::repeat_byte(1)), None]);
+
+        // Blobs fetched from another source complete the cached blobs of the block
+        provider.insert_blobs(block, [(hashes[1].hash, Blob::repeat_byte(2))]);
+        assert_eq!(
+            provider.get_blobs(&block, &hashes),
+            vec![Some(Blob::repeat_byte(1)), Some(Blob::repeat_byte(2))]
+        );
+        assert_eq!(provider.blocks_to_blob_hashes[&block].len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_layered_fetches_missing_blobs_in_requested_order() {
+        let blob_sidecar = sidecar(&[1, 2, 3, 4]);
+        let hashes = indexed_hashes(&blob_sidecar);
+        let online = StubBlobProvider {
+            blobs: hashes.iter().map(|h| h.hash).zip(blob_sidecar.blobs.clone()).collect(),
+            ..Default::default()
+        };
+        let mut provider = LayeredBlobProvider::with_online_provider(online);
+
+        // Only the blobs at indices 1 and 3 are cached
+        let block = BlockInfo { hash: B256::with_last_byte(1), ..Default::default() };
+        provider
+            .memory
+            .lock()
+            .insert_blobs(block.hash, [1, 3].map(|i| (hashes[i].hash, blob_sidecar.blobs[i])));
+
+        let requested = [3, 2, 1, 0].map(|i| hashes[i].clone());
+        let blobs = provider.get_blobs(&block, &requested).await.unwrap();
+        assert_eq!(blobs, [4, 3, 2, 1].map(Blob::repeat_byte).to_vec());
+
+        // The missing blobs are fetched once, by ascending index, and cached afterwards
+        assert_eq!(provider.online.requests, vec![vec![hashes[0].clone(), hashes[2].clone()]]);
+        provider.get_blobs(&block, &requested).await.unwrap();
+        assert_eq!(provider.online.requests.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_layered_fails_on_missing_online_blobs() {
+        let mut provider = LayeredBlobProvider::with_online_provider(StubBlobProvider::default());
+        let block = BlockInfo { hash: B256::with_last_byte(1), ..Default::default() };
+
+        let hashes = indexed_hashes(&sidecar(&[1]));
+        assert!(provider.get_blobs(&block, &hashes).await.is_err());
+        assert!(provider.memory.lock().blocks_to_blob_hashes.is_empty());
+    }
+
+    /// Returns the sidecar of the zero blob, whose commitment and proof are the point at infinity.
+    fn zero_blob_sidecar(index: u64) -> (BlobSidecar, IndexedBlobHash) {
+        let mut infinity = Bytes48::ZERO;
+        infinity[0] = 0xc0;
+        let sidecar =
+            BlobSidecar { index, blob: Blob::ZERO, kzg_commitment: infinity, kzg_proof: infinity };
+        let hash =
+            IndexedBlobHash { index: index as usize, hash: kzg_to_versioned_hash(&infinity[..]) };
+        (sidecar, hash)
+    }
+
+    #[test]
+    fn test_verify_blobs() {
+        let (first, first_hash) = zero_blob_sidecar(0);
+        let (second, second_hash) = zero_blob_sidecar(2);
+        let sidecars = vec![first, second];
+
+        let blobs = verify_blobs(&sidecars, &[second_hash.clone(), first_hash.clone()]).unwrap();
+        assert_eq!(blobs, vec![Blob::ZERO, Blob::ZERO]);
+
+        // Missing sidecar
+        let missing = IndexedBlobHash { index: 1, hash: first_hash.hash };
+        assert!(verify_blobs(&sidecars, &[missing]).is_err());
+
+        // Commitment not matching the requested versioned hash
+        let wrong_hash = IndexedBlobHash { index: 0, hash: B256::with_last_byte(1) };
+        assert!(verify_blobs(&sidecars, &[wrong_hash]).is_err());
+
+        // Blob not matching its commitment and proof
+        let mut tampered = sidecars.clone();
+        tampered[0].blob[31] = 1;
+        assert!(verify_blobs(&tampered, &[first_hash]).is_err());
     }
 }
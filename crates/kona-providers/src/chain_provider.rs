@@ -1,15 +1,22 @@
 //! Chain Provider
 
-use alloc::{collections::vec_deque::VecDeque, sync::Arc};
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+};
+use core::fmt::Debug;
 use hashbrown::HashMap;
 
 use alloy::{
     consensus::{
-        Header, Receipt, Signed, TxEip1559, TxEip2930, TxEip4844, TxEip4844Variant, TxEnvelope,
-        TxLegacy,
+        Header, Receipt, ReceiptEnvelope, ReceiptWithBloom, Signed, TxEip1559, TxEip2930,
+        TxEip4844, TxEip4844Variant, TxEip7702, TxEnvelope, TxLegacy, TxType,
+    },
+    eips::{
+        eip2930::{AccessList, AccessListItem},
+        BlockNumHash,
     },
-    eips::BlockNumHash,
-    primitives::B256,
+    primitives::{Parity, B256},
     signers::Signature,
 };
 use alloy_rlp::Decodable;
@@ -18,13 +25,18 @@ use eyre::eyre;
 use kona_derive::traits::ChainProvider;
 use op_alloy_protocol::BlockInfo;
 use parking_lot::RwLock;
-use reth::{primitives::Transaction, providers::Chain};
+use reth::{
+    primitives::{SealedHeader, Transaction},
+    providers::{BlockReader, Chain, HeaderProvider, ReceiptProvider},
+};
+use tracing::{debug, warn};
 
 /// An in-memory [ChainProvider] that stores chain data,
 /// meant to be shared between multiple readers.
 ///
-/// This provider uses a ring buffer to limit capacity
-/// to avoid storing an unbounded amount of data in memory.
+/// This provider only keeps the highest blocks of the canonical chain, up to its
+/// capacity, to avoid storing an unbounded amount of data in memory. Reverted
+/// blocks are removed so that they are never served.
 #[derive(Debug, Clone)]
 pub struct InMemoryChainProvider(Arc<RwLock<InMemoryChainProviderInner>>);
 
@@ -39,6 +51,27 @@ impl InMemoryChainProvider {
         self.0.write().commit(chain);
     }
 
+    /// Removes the blocks of a reverted Chain from the provider.
+    pub fn revert(&mut self, chain: Arc<Chain>) {
+        self.0.write().revert(chain);
+    }
+
+    /// Returns all typed receipts in the block with the given hash, with their logs bloom,
+    /// or an error if the block is not in the cache.
+    pub fn receipt_envelopes_by_hash(&self, hash: B256) -> eyre::Result<Vec<ReceiptEnvelope>> {
+        self.0
+            .read()
+            .hash_to_receipts
+            .get(&hash)
+            .cloned()
+            .ok_or_else(|| eyre!("Receipts not found"))
+    }
+
+    /// Removes all the blocks above the given number, e.g. after an L1 reorg.
+    pub fn revert_to(&mut self, number: u64) {
+        self.0.write().revert_to(number);
+    }
+
     /// Inserts the L2 genesis [BlockNumHash] into the provider.
     pub fn insert_l2_genesis_block(&mut self, block: BlockNumHash) {
         self.0.write().insert_l2_genesis_block(block);
@@ -52,10 +85,15 @@ struct InMemoryChainProviderInner {
     /// This is used to prevent unbounded memory usage.
     capacity: usize,
 
-    /// The order in which keys were inserted into the provider.
-    /// This is used to evict the oldest items when the provider
-    /// reaches its capacity.
-    key_order: VecDeque<B256>,
+    /// Maps block numbers to the [B256] hash of the canonical block at that height.
+    /// This is used to look up blocks by number, and to evict the lowest blocks
+    /// when the provider reaches its capacity.
+    number_to_hash: BTreeMap<u64, B256>,
+
+    /// The [B256] hashes of the blocks fetched by hash, in insertion order.
+    /// These blocks may not be canonical, so they are not part of the number index
+    /// and are evicted separately, oldest first.
+    unindexed: VecDeque<B256>,
 
     /// Maps [B256] hash to [Header].
     hash_to_header: HashMap<B256, Header>,
@@ -63,8 +101,12 @@ struct InMemoryChainProviderInner {
     /// Maps [B256] hash to [BlockInfo].
     hash_to_block_info: HashMap<B256, BlockInfo>,
 
-    /// Maps [B256] hash to [Vec]<[Receipt]>.
-    hash_to_receipts: HashMap<B256, Vec<Receipt>>,
+    /// Maps [B256] hash to [Vec]<[ReceiptEnvelope]>.
+    hash_to_receipts: HashMap<B256, Vec<ReceiptEnvelope>>,
+
+    /// Maps [B256] hash to the [Receipt]s fetched from another source while the transactions
+    /// of the block were not cached, so that their types are unknown.
+    hash_to_untyped_receipts: HashMap<B256, Vec<Receipt>>,
 
     /// Maps a [B256] hash to a [Vec]<[TxEnvelope]>.
     hash_to_txs: HashMap<B256, Vec<TxEnvelope>>,
@@ -74,34 +116,160 @@ impl InMemoryChainProviderInner {
     fn with_capacity(cap: usize) -> Self {
         Self {
             capacity: cap,
-            key_order: VecDeque::new(),
+            number_to_hash: BTreeMap::new(),
+            unindexed: VecDeque::with_capacity(cap),
             hash_to_header: HashMap::with_capacity(cap),
             hash_to_block_info: HashMap::with_capacity(cap),
             hash_to_receipts: HashMap::with_capacity(cap),
+            hash_to_untyped_receipts: HashMap::new(),
             hash_to_txs: HashMap::with_capacity(cap),
         }
     }
 
     /// Commits Chain state to the provider.
     fn commit(&mut self, chain: Arc<Chain>) {
-        // Remove the oldest items if the provider is at capacity.
-        self.key_order.extend(chain.headers().map(|h| h.hash()));
-        if self.key_order.len() > self.capacity {
-            let to_remove = self.key_order.len() - self.capacity;
-            for _ in 0..to_remove {
-                if let Some(key) = self.key_order.pop_front() {
-                    self.hash_to_header.remove(&key);
-                    self.hash_to_block_info.remove(&key);
-                    self.hash_to_receipts.remove(&key);
-                    self.hash_to_txs.remove(&key);
-                }
-            }
+        for header in chain.headers() {
+            self.index_block(header.number, header.hash());
         }
 
         self.commit_headers(&chain);
         self.commit_block_infos(&chain);
         self.commit_receipts(&chain);
         self.commit_txs(&chain);
+        self.evict();
+    }
+
+    /// Indexes the block with the given number and hash as canonical.
+    /// It replaces any previous block at the same height.
+    fn index_block(&mut self, number: u64, hash: B256) {
+        if let Some(replaced) = self.number_to_hash.insert(number, hash) {
+            if replaced != hash {
+                self.remove_block(&replaced);
+            }
+        }
+    }
+
+    /// Removes the lowest blocks if the provider is over capacity,
+    /// and the oldest blocks fetched by hash if there are more of them than the capacity.
+    fn evict(&mut self) {
+        while self.number_to_hash.len() > self.capacity {
+            if let Some((_, hash)) = self.number_to_hash.pop_first() {
+                self.remove_block(&hash);
+            }
+        }
+        while self.unindexed.len() > self.capacity {
+            if let Some(hash) = self.unindexed.pop_front() {
+                // The block may have been indexed since it was fetched by hash.
+                if !self.is_indexed(&hash) {
+                    self.remove_block(&hash);
+                }
+            }
+        }
+    }
+
+    /// Tracks a block fetched by hash for eviction, unless it is part of the number index.
+    fn track_unindexed(&mut self, hash: B256) {
+        if !self.is_indexed(&hash) && !self.unindexed.contains(&hash) {
+            self.unindexed.push_back(hash);
+        }
+        self.evict();
+    }
+
+    /// Inserts a [Header] fetched by hash from another source.
+    ///
+    /// The block is not indexed by number, as it may not be canonical.
+    fn insert_header(&mut self, hash: B256, header: Header) {
+        self.hash_to_header.insert(hash, header);
+        self.track_unindexed(hash);
+    }
+
+    /// Inserts a [BlockInfo] fetched by number from another source.
+    fn insert_block_info(&mut self, block_info: BlockInfo) {
+        self.index_block(block_info.number, block_info.hash);
+        self.hash_to_block_info.insert(block_info.hash, block_info);
+        self.evict();
+    }
+
+    /// Inserts a [BlockInfo] fetched by hash from another source.
+    ///
+    /// The block is not indexed by number, as it may not be canonical.
+    fn insert_block_info_by_hash(&mut self, block_info: BlockInfo) {
+        self.hash_to_block_info.insert(block_info.hash, block_info);
+        self.track_unindexed(block_info.hash);
+    }
+
+    /// Inserts the [Receipt]s of a block fetched from another source.
+    ///
+    /// Receipts are only cached if their block is known, as they can't be evicted otherwise.
+    /// They are typed after the transactions of the block if those are cached, and kept
+    /// untyped otherwise.
+    fn insert_receipts(&mut self, hash: B256, receipts: Vec<Receipt>) {
+        if !self.is_known(&hash) {
+            return;
+        }
+        let Some(txs) = self.hash_to_txs.get(&hash).filter(|txs| txs.len() == receipts.len())
+        else {
+            self.hash_to_untyped_receipts.insert(hash, receipts);
+            return;
+        };
+
+        let receipts = txs
+            .iter()
+            .zip(receipts)
+            .map(|(tx, receipt)| {
+                let logs_bloom = receipt.bloom_slow();
+                receipt_envelope(tx.tx_type(), ReceiptWithBloom { receipt, logs_bloom })
+            })
+            .collect();
+        self.hash_to_receipts.insert(hash, receipts);
+    }
+
+    /// Inserts the [TxEnvelope]s of a block fetched from another source.
+    ///
+    /// Transactions are only cached if their block is known, as they can't be evicted otherwise.
+    fn insert_txs(&mut self, hash: B256, txs: Vec<TxEnvelope>) {
+        if self.is_known(&hash) {
+            self.hash_to_txs.insert(hash, txs);
+        }
+    }
+
+    /// Returns true if the block with the given hash is cached, either as part of the number
+    /// index or as a block fetched by hash.
+    fn is_known(&self, hash: &B256) -> bool {
+        self.hash_to_block_info.contains_key(hash) || self.hash_to_header.contains_key(hash)
+    }
+
+    /// Returns true if the block with the given hash is part of the number index.
+    fn is_indexed(&self, hash: &B256) -> bool {
+        let number = self.hash_to_block_info.get(hash).map(|b| b.number);
+        let number = number.or_else(|| self.hash_to_header.get(hash).map(|h| h.number));
+        number.is_some_and(|n| self.number_to_hash.get(&n) == Some(hash))
+    }
+
+    /// Removes all the blocks above the given number.
+    fn revert_to(&mut self, number: u64) {
+        for (_, hash) in self.number_to_hash.split_off(&(number + 1)) {
+            self.remove_block(&hash);
+        }
+    }
+
+    /// Removes the blocks of a reverted Chain from the provider.
+    fn revert(&mut self, chain: Arc<Chain>) {
+        for header in chain.headers() {
+            if self.number_to_hash.get(&header.number) == Some(&header.hash()) {
+                self.number_to_hash.remove(&header.number);
+            }
+            self.remove_block(&header.hash());
+        }
+    }
+
+    /// Removes all the data of the block with the given hash.
+    fn remove_block(&mut self, hash: &B256) {
+        self.hash_to_header.remove(hash);
+        self.hash_to_block_info.remove(hash);
+        self.hash_to_receipts.remove(hash);
+        self.hash_to_untyped_receipts.remove(hash);
+        self.hash_to_txs.remove(hash);
     }
 
     /// Commits [Header]s to the provider.
@@ -129,6 +297,7 @@ impl InMemoryChainProviderInner {
 
     /// Inserts the L2 genesis [BlockNumHash] into the provider.
     fn insert_l2_genesis_block(&mut self, block: BlockNumHash) {
+        self.number_to_hash.entry(block.number).or_insert(block.hash);
         self.hash_to_block_info.insert(
             block.hash,
             BlockInfo {
@@ -140,30 +309,39 @@ impl InMemoryChainProviderInner {
         );
     }
 
-    /// Commits [Receipt]s to the provider.
+    /// Commits [ReceiptEnvelope]s to the provider.
     fn commit_receipts(&mut self, chain: &Arc<Chain>) {
-        for (b, receipt) in chain.blocks_and_receipts() {
-            self.hash_to_receipts.insert(
-                b.hash(),
-                receipt
-                    .iter()
-                    .flat_map(|r| {
-                        r.as_ref().map(|r| Receipt {
-                            cumulative_gas_used: r.cumulative_gas_used as u128,
-                            logs: r.logs.clone(),
-                            status: alloy::consensus::Eip658Value::Eip658(r.success),
-                        })
-                    })
-                    .collect(),
-            );
+        for (b, receipts) in chain.blocks_and_receipts() {
+            // The receipts of a block are not cached if any of them is missing (e.g. pruned)
+            // or can't be converted, as they are looked up by transaction index.
+            let receipts = receipts
+                .iter()
+                .map(|r| {
+                    r.as_ref()
+                        .ok_or_else(|| eyre!("Receipt missing"))
+                        .and_then(|r| reth_to_alloy_receipt(r).map_err(|e| eyre!(e)))
+                })
+                .collect::<eyre::Result<_>>();
+            match receipts {
+                Ok(receipts) => {
+                    self.hash_to_receipts.insert(b.hash(), receipts);
+                }
+                Err(e) => warn!("Failed to convert the receipts of block {}: {}", b.hash(), e),
+            }
         }
     }
 
     /// Commits [TxEnvelope]s to the provider.
     fn commit_txs(&mut self, chain: &Arc<Chain>) {
         for b in chain.blocks_iter() {
-            let txs = b.transactions().flat_map(reth_to_alloy_tx).collect();
-            self.hash_to_txs.insert(b.hash(), txs);
+            // The transactions of a block are not cached if any of them can't be converted,
+            // so that the error surfaces when they are requested.
+            match b.transactions().map(reth_to_alloy_tx).collect::<Result<_, _>>() {
+                Ok(txs) => {
+                    self.hash_to_txs.insert(b.hash(), txs);
+                }
+                Err(e) => warn!("Failed to convert the transactions of block {}: {}", b.hash(), e),
+            }
         }
     }
 }
@@ -185,11 +363,11 @@ impl ChainProvider for InMemoryChainProvider {
     /// Returns the block at the given number, or an error if the block does not exist in the data
     /// source.
     async fn block_info_by_number(&mut self, number: u64) -> eyre::Result<BlockInfo> {
-        self.0
-            .read()
-            .hash_to_block_info
-            .values()
-            .find(|bi| bi.number == number)
+        let inner = self.0.read();
+        inner
+            .number_to_hash
+            .get(&number)
+            .and_then(|hash| inner.hash_to_block_info.get(hash))
             .cloned()
             .ok_or_else(|| eyre!("Block not found"))
     }
@@ -197,12 +375,10 @@ impl ChainProvider for InMemoryChainProvider {
     /// Returns all receipts in the block with the given hash, or an error if the block does not
     /// exist in the data source.
     async fn receipts_by_hash(&mut self, hash: B256) -> eyre::Result<Vec<Receipt>> {
-        self.0
-            .read()
-            .hash_to_receipts
-            .get(&hash)
-            .cloned()
-            .ok_or_else(|| eyre!("Receipts not found"))
+        match self.receipt_envelopes_by_hash(hash) {
+            Ok(receipts) => into_receipts(&receipts),
+            Err(e) => self.0.read().hash_to_untyped_receipts.get(&hash).cloned().ok_or(e),
+        }
     }
 
     /// Returns block info and transactions for the given block hash.
@@ -225,6 +401,217 @@ impl ChainProvider for InMemoryChainProvider {
     }
 }
 
+/// Layered [ChainProvider] for the Kona derivation pipeline.
+///
+/// This provider wraps different chain data sources in an ordered manner:
+/// - First, it attempts to serve data from an in-memory cache.
+/// - If the data is not found, it fetches it from the fallback provider (e.g. over RPC or from the
+///   database of a reth node), and populates the cache with it.
+///
+/// Blocks fetched by hash are cached without being indexed by number, as they may not be
+/// part of the canonical chain.
+#[derive(Debug, Clone)]
+pub struct LayeredChainProvider<F> {
+    /// In-memory cache, filled with the chains committed by the node (in ExEx mode)
+    /// and with the data fetched from the fallback provider.
+    memory: InMemoryChainProvider,
+    /// Fallback provider, used for the data missing from the cache.
+    fallback: F,
+}
+
+impl<F> LayeredChainProvider<F> {
+    /// Creates a new [LayeredChainProvider] with the given cache and fallback provider.
+    pub const fn new(memory: InMemoryChainProvider, fallback: F) -> Self {
+        Self { memory, fallback }
+    }
+}
+
+#[async_trait]
+impl<F> ChainProvider for LayeredChainProvider<F>
+where
+    F: ChainProvider + Send + Sync,
+{
+    type Error = eyre::Error;
+
+    /// Fetch the L1 [Header] for the given [B256] hash.
+    async fn header_by_hash(&mut self, hash: B256) -> eyre::Result<Header> {
+        if let Ok(header) = self.memory.header_by_hash(hash).await {
+            return Ok(header);
+        }
+
+        debug!("Header {} not in memory, using fallback provider", hash);
+        let header = self.fallback.header_by_hash(hash).await.map_err(|e| eyre!(e.to_string()))?;
+        self.memory.0.write().insert_header(hash, header.clone());
+        Ok(header)
+    }
+
+    /// Returns the block at the given number, or an error if the block does not exist in the data
+    /// source.
+    async fn block_info_by_number(&mut self, number: u64) -> eyre::Result<BlockInfo> {
+        if let Ok(block_info) = self.memory.block_info_by_number(number).await {
+            return Ok(block_info);
+        }
+
+        debug!("Block {} not in memory, using fallback provider", number);
+        let block_info =
+            self.fallback.block_info_by_number(number).await.map_err(|e| eyre!(e.to_string()))?;
+        self.memory.0.write().insert_block_info(block_info);
+        Ok(block_info)
+    }
+
+    /// Returns all receipts in the block with the given hash, or an error if the block does not
+    /// exist in the data source.
+    async fn receipts_by_hash(&mut self, hash: B256) -> eyre::Result<Vec<Receipt>> {
+        if let Ok(receipts) = self.memory.receipts_by_hash(hash).await {
+            return Ok(receipts);
+        }
+
+        debug!("Receipts of block {} not in memory, using fallback provider", hash);
+        let receipts =
+            self.fallback.receipts_by_hash(hash).await.map_err(|e| eyre!(e.to_string()))?;
+        self.memory.0.write().insert_receipts(hash, receipts.clone());
+        Ok(receipts)
+    }
+
+    /// Returns block info and transactions for the given block hash.
+    async fn block_info_and_transactions_by_hash(
+        &mut self,
+        hash: B256,
+    ) -> eyre::Result<(BlockInfo, Vec<TxEnvelope>)> {
+        if let Ok(block) = self.memory.block_info_and_transactions_by_hash(hash).await {
+            return Ok(block);
+        }
+
+        debug!("Block {} not in memory, using fallback provider", hash);
+        let (block_info, txs) = self
+            .fallback
+            .block_info_and_transactions_by_hash(hash)
+            .await
+            .map_err(|e| eyre!(e.to_string()))?;
+        let mut memory = self.memory.0.write();
+        memory.insert_block_info_by_hash(block_info);
+        memory.insert_txs(hash, txs.clone());
+        Ok((block_info, txs))
+    }
+}
+
+/// A [ChainProvider] that reads L1 data from the database of a reth node.
+///
+/// This is meant to be used as the fallback of a [LayeredChainProvider] in ExEx mode,
+/// to serve blocks that are older than the cache, e.g. when deriving from an old L2 tip
+/// or after a deep reset.
+#[derive(Clone)]
+pub struct RethChainProvider<P> {
+    /// The reth database provider.
+    provider: P,
+}
+
+impl<P> RethChainProvider<P> {
+    /// Create a new [RethChainProvider] with the given reth provider.
+    pub const fn new(provider: P) -> Self {
+        Self { provider }
+    }
+}
+
+impl<P> Debug for RethChainProvider<P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RethChainProvider").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<P> ChainProvider for RethChainProvider<P>
+where
+    P: BlockReader + HeaderProvider + ReceiptProvider + Clone + Send + Sync,
+{
+    type Error = eyre::Error;
+
+    /// Fetch the L1 [Header] for the given [B256] hash.
+    async fn header_by_hash(&mut self, hash: B256) -> eyre::Result<Header> {
+        let header = self
+            .provider
+            .header(&hash)?
+            .ok_or_else(|| eyre!("Header not found for hash: {}", hash))?;
+        Ok(reth_to_alloy_header(&SealedHeader::new(header, hash)))
+    }
+
+    /// Returns the block at the given number, or an error if the block does not exist in the data
+    /// source.
+    async fn block_info_by_number(&mut self, number: u64) -> eyre::Result<BlockInfo> {
+        let header =
+            self.provider.sealed_header(number)?.ok_or_else(|| eyre!("Block not found"))?;
+        Ok(BlockInfo {
+            hash: header.hash(),
+            number: header.number,
+            timestamp: header.timestamp,
+            parent_hash: header.parent_hash,
+        })
+    }
+
+    /// Returns all receipts in the block with the given hash, or an error if the block does not
+    /// exist in the data source.
+    async fn receipts_by_hash(&mut self, hash: B256) -> eyre::Result<Vec<Receipt>> {
+        let receipts = self
+            .provider
+            .receipts_by_block(hash.into())?
+            .ok_or_else(|| eyre!("Receipts not found"))?;
+        let receipts = receipts.iter().map(reth_to_alloy_receipt).collect::<Result<Vec<_>, _>>()?;
+        into_receipts(&receipts)
+    }
+
+    /// Returns block info and transactions for the given block hash.
+    async fn block_info_and_transactions_by_hash(
+        &mut self,
+        hash: B256,
+    ) -> eyre::Result<(BlockInfo, Vec<TxEnvelope>)> {
+        let block = self.provider.block_by_hash(hash)?.ok_or_else(|| eyre!("Block not found"))?;
+        let block_info = BlockInfo {
+            hash,
+            number: block.number,
+            timestamp: block.timestamp,
+            parent_hash: block.parent_hash,
+        };
+        let txs = block.body.iter().map(reth_to_alloy_tx).collect::<Result<_, _>>()?;
+        Ok((block_info, txs))
+    }
+}
+
+/// Converts a reth [Receipt](reth::primitives::Receipt) into an alloy [ReceiptEnvelope],
+/// keeping its transaction type and logs bloom.
+pub fn reth_to_alloy_receipt(
+    receipt: &reth::primitives::Receipt,
+) -> Result<ReceiptEnvelope, TxConversionError> {
+    let tx_type = u8::from(receipt.tx_type);
+    let tx_type =
+        TxType::try_from(tx_type).map_err(|_| TxConversionError::UnsupportedTxType(tx_type))?;
+    let logs_bloom = receipt.bloom_slow();
+    let receipt = Receipt {
+        cumulative_gas_used: receipt.cumulative_gas_used as u128,
+        logs: receipt.logs.clone(),
+        status: alloy::consensus::Eip658Value::Eip658(receipt.success),
+    };
+    Ok(receipt_envelope(tx_type, ReceiptWithBloom { receipt, logs_bloom }))
+}
+
+/// Wraps a [ReceiptWithBloom] in the [ReceiptEnvelope] of the given transaction type.
+fn receipt_envelope(tx_type: TxType, receipt: ReceiptWithBloom) -> ReceiptEnvelope {
+    match tx_type {
+        TxType::Legacy => ReceiptEnvelope::Legacy(receipt),
+        TxType::Eip2930 => ReceiptEnvelope::Eip2930(receipt),
+        TxType::Eip1559 => ReceiptEnvelope::Eip1559(receipt),
+        TxType::Eip4844 => ReceiptEnvelope::Eip4844(receipt),
+        TxType::Eip7702 => ReceiptEnvelope::Eip7702(receipt),
+    }
+}
+
+/// Strips the type and logs bloom of [ReceiptEnvelope]s, as returned by the [ChainProvider].
+fn into_receipts(receipts: &[ReceiptEnvelope]) -> eyre::Result<Vec<Receipt>> {
+    receipts
+        .iter()
+        .map(|r| r.as_receipt().cloned().ok_or_else(|| eyre!("Unknown receipt type")))
+        .collect()
+}
+
 pub fn reth_to_alloy_header(header: &reth::primitives::SealedHeader) -> Header {
     Header {
         parent_hash: header.parent_hash,
@@ -251,12 +638,45 @@ pub fn reth_to_alloy_header(header: &reth::primitives::SealedHeader) -> Header {
     }
 }
 
-pub fn reth_to_alloy_tx(tx: &reth::primitives::TransactionSigned) -> Option<TxEnvelope> {
+/// An error converting a reth transaction into an alloy [TxEnvelope].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxConversionError {
+    /// The signature of the transaction could not be decoded.
+    InvalidSignature(alloy_rlp::Error),
+    /// The transaction type is not supported on L1, e.g. deposit transactions.
+    UnsupportedTxType(u8),
+}
+
+impl core::fmt::Display for TxConversionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidSignature(e) => write!(f, "invalid transaction signature: {}", e),
+            Self::UnsupportedTxType(ty) => write!(f, "unsupported transaction type: {}", ty),
+        }
+    }
+}
+
+impl core::error::Error for TxConversionError {}
+
+/// Converts a reth [TransactionSigned](reth::primitives::TransactionSigned) into an
+/// alloy [TxEnvelope], keeping its hash and signature.
+///
+/// Deposit transactions are rejected with [TxConversionError::UnsupportedTxType]: they only
+/// exist on L2, and [TxEnvelope] has no variant for them.
+pub fn reth_to_alloy_tx(
+    tx: &reth::primitives::TransactionSigned,
+) -> Result<TxEnvelope, TxConversionError> {
     let mut buf = Vec::new();
     tx.signature.encode(&mut buf);
-    let sig = Signature::decode(&mut buf.as_slice()).ok()?;
+    let sig =
+        Signature::decode(&mut buf.as_slice()).map_err(TxConversionError::InvalidSignature)?;
     let new = match &tx.transaction {
         Transaction::Legacy(l) => {
+            // Legacy transactions encode the chain id in the signature parity (EIP-155)
+            let sig = match l.chain_id {
+                Some(chain_id) => sig.with_chain_id(chain_id),
+                None => sig.with_parity(Parity::NonEip155(sig.v().y_parity())),
+            };
             let legacy_tx = TxLegacy {
                 chain_id: l.chain_id,
                 nonce: l.nonce,
@@ -277,19 +697,9 @@ pub fn reth_to_alloy_tx(tx: &reth::primitives::TransactionSigned) -> Option<TxEn
                 to: e.to,
                 value: e.value,
                 input: e.input.clone(),
-                access_list: alloy::eips::eip2930::AccessList(
-                    e.access_list
-                        .0
-                        .clone()
-                        .into_iter()
-                        .map(|item| alloy::eips::eip2930::AccessListItem {
-                            address: item.address,
-                            storage_keys: item.storage_keys.clone(),
-                        })
-                        .collect(),
-                ),
+                access_list: reth_to_alloy_access_list(&e.access_list),
             };
-            TxEnvelope::Eip2930(Signed::new_unchecked(eip_tx, sig, tx.hash))
+            TxEnvelope::Eip2930(Signed::new_unchecked(eip_tx, sig.with_parity_bool(), tx.hash))
         }
         Transaction::Eip1559(e) => {
             let eip_tx = TxEip1559 {
@@ -301,19 +711,9 @@ pub fn reth_to_alloy_tx(tx: &reth::primitives::TransactionSigned) -> Option<TxEn
                 to: e.to,
                 value: e.value,
                 input: e.input.clone(),
-                access_list: alloy::eips::eip2930::AccessList(
-                    e.access_list
-                        .0
-                        .clone()
-                        .into_iter()
-                        .map(|item| alloy::eips::eip2930::AccessListItem {
-                            address: item.address,
-                            storage_keys: item.storage_keys.clone(),
-                        })
-                        .collect(),
-                ),
+                access_list: reth_to_alloy_access_list(&e.access_list),
             };
-            TxEnvelope::Eip1559(Signed::new_unchecked(eip_tx, sig, tx.hash))
+            TxEnvelope::Eip1559(Signed::new_unchecked(eip_tx, sig.with_parity_bool(), tx.hash))
         }
         Transaction::Eip4844(e) => {
             let eip_tx = TxEip4844 {
@@ -327,25 +727,422 @@ pub fn reth_to_alloy_tx(tx: &reth::primitives::TransactionSigned) -> Option<TxEn
                 to: e.to,
                 value: e.value,
                 input: e.input.clone(),
-                access_list: alloy::eips::eip2930::AccessList(
-                    e.access_list
-                        .0
-                        .clone()
-                        .into_iter()
-                        .map(|item| alloy::eips::eip2930::AccessListItem {
-                            address: item.address,
-                            storage_keys: item.storage_keys.clone(),
-                        })
-                        .collect(),
-                ),
+                access_list: reth_to_alloy_access_list(&e.access_list),
             };
             TxEnvelope::Eip4844(Signed::new_unchecked(
                 TxEip4844Variant::TxEip4844(eip_tx),
-                sig,
+                sig.with_parity_bool(),
                 tx.hash,
             ))
         }
-        Transaction::Eip7702(_) => unimplemented!("EIP-7702 not implemented"),
+        Transaction::Eip7702(e) => {
+            let eip_tx = TxEip7702 {
+                chain_id: e.chain_id,
+                nonce: e.nonce,
+                gas_limit: e.gas_limit,
+                max_fee_per_gas: e.max_fee_per_gas,
+                max_priority_fee_per_gas: e.max_priority_fee_per_gas,
+                to: e.to,
+                value: e.value,
+                access_list: reth_to_alloy_access_list(&e.access_list),
+                authorization_list: e.authorization_list.clone(),
+                input: e.input.clone(),
+            };
+            TxEnvelope::Eip7702(Signed::new_unchecked(eip_tx, sig.with_parity_bool(), tx.hash))
+        }
+        // Deposit transactions only exist when reth is built for the OP stack,
+        // and are never part of an L1 block.
+        #[cfg(feature = "optimism")]
+        Transaction::Deposit(_) => {
+            return Err(TxConversionError::UnsupportedTxType(tx.tx_type().into()))
+        }
     };
-    Some(new)
+    Ok(new)
+}
+
+/// Converts a reth access list into an alloy [AccessList].
+fn reth_to_alloy_access_list(access_list: &reth::primitives::AccessList) -> AccessList {
+    AccessList(
+        access_list
+            .0
+            .iter()
+            .map(|item| AccessListItem {
+                address: item.address,
+                storage_keys: item.storage_keys.clone(),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        consensus::SignableTransaction,
+        eips::eip2718::Encodable2718,
+        primitives::{Address, Bloom, Bytes, ChainId, Log, U256},
+    };
+    use arbitrary::Unstructured;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use reth::{
+        primitives::{
+            BlockBody, Header as RethHeader, SealedBlock, SealedBlockWithSenders,
+            TransactionSigned, TxType as RethTxType,
+        },
+        providers::ExecutionOutcome,
+    };
+
+    fn block(number: u64, parent_hash: B256, fork: u8) -> SealedBlockWithSenders {
+        let header = RethHeader { number, parent_hash, ..Default::default() };
+        let mut hash = B256::left_padding_from(&number.to_be_bytes());
+        hash.0[0] = fork;
+        let block = SealedBlock::new(SealedHeader::new(header, hash), BlockBody::default());
+        SealedBlockWithSenders::new(block, vec![]).expect("no transactions")
+    }
+
+    /// Builds a chain of `len` blocks on top of the given parent, tagged with `fork`.
+    fn chain(parent: (u64, B256), len: u64, fork: u8) -> Arc<Chain> {
+        let mut blocks = Vec::new();
+        let (mut number, mut parent_hash) = parent;
+        for _ in 0..len {
+            number += 1;
+            let block = block(number, parent_hash, fork);
+            parent_hash = block.hash();
+            blocks.push(block);
+        }
+        Arc::new(Chain::new(blocks, ExecutionOutcome::default(), None))
+    }
+
+    fn tip(chain: &Chain) -> (u64, B256) {
+        let tip = chain.tip();
+        (tip.number, tip.hash())
+    }
+
+    #[tokio::test]
+    async fn test_block_info_by_number() {
+        let mut provider = InMemoryChainProvider::with_capacity(16);
+        let chain = chain((0, B256::ZERO), 4, 0xa);
+        provider.commit(chain.clone());
+
+        for block in chain.blocks_iter() {
+            let info = provider.block_info_by_number(block.number).await.unwrap();
+            assert_eq!(info.hash, block.hash());
+        }
+        assert!(provider.block_info_by_number(5).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reorged_out_blocks_are_never_served() {
+        let mut provider = InMemoryChainProvider::with_capacity(16);
+        let old = chain((0, B256::ZERO), 4, 0xa);
+        provider.commit(old.clone());
+
+        // Reorg out blocks 3 and 4 with a longer fork
+        let fork_point = old.blocks().get(&2).map(|b| (b.number, b.hash())).unwrap();
+        let new = chain(fork_point, 3, 0xb);
+        let reverted = old.blocks_iter().filter(|b| b.number > 2).cloned();
+        provider.revert(Arc::new(Chain::new(reverted, ExecutionOutcome::default(), None)));
+        provider.commit(new.clone());
+
+        for block in old.blocks_iter().filter(|b| b.number > 2) {
+            assert!(provider.header_by_hash(block.hash()).await.is_err());
+            assert!(provider.block_info_and_transactions_by_hash(block.hash()).await.is_err());
+        }
+        for block in new.blocks_iter() {
+            let info = provider.block_info_by_number(block.number).await.unwrap();
+            assert_eq!(info.hash, block.hash());
+        }
+        assert_eq!(provider.block_info_by_number(2).await.unwrap().hash, fork_point.1);
+    }
+
+    #[tokio::test]
+    async fn test_commit_replaces_fork_blocks_without_revert() {
+        let mut provider = InMemoryChainProvider::with_capacity(16);
+        let old = chain((0, B256::ZERO), 3, 0xa);
+        provider.commit(old.clone());
+
+        let new = chain((0, B256::ZERO), 3, 0xb);
+        provider.commit(new.clone());
+
+        for (old, new) in old.blocks_iter().zip(new.blocks_iter()) {
+            assert!(provider.header_by_hash(old.hash()).await.is_err());
+            let info = provider.block_info_by_number(new.number).await.unwrap();
+            assert_eq!(info.hash, new.hash());
+        }
+    }
+
+    /// A fallback provider serving the blocks of a chain, and counting the calls.
+    #[derive(Debug, Clone)]
+    struct CountingProvider {
+        blocks: Arc<Chain>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl CountingProvider {
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::Relaxed)
+        }
+
+        fn find(&self, f: impl Fn(&SealedBlockWithSenders) -> bool) -> eyre::Result<BlockInfo> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.blocks
+                .blocks_iter()
+                .find(|b| f(b))
+                .map(|b| BlockInfo {
+                    hash: b.hash(),
+                    number: b.number,
+                    timestamp: b.timestamp,
+                    parent_hash: b.parent_hash,
+                })
+                .ok_or_else(|| eyre!("Block not found"))
+        }
+    }
+
+    #[async_trait]
+    impl ChainProvider for CountingProvider {
+        type Error = eyre::Error;
+
+        async fn header_by_hash(&mut self, hash: B256) -> eyre::Result<Header> {
+            let info = self.find(|b| b.hash() == hash)?;
+            Ok(Header { number: info.number, parent_hash: info.parent_hash, ..Default::default() })
+        }
+
+        async fn block_info_by_number(&mut self, number: u64) -> eyre::Result<BlockInfo> {
+            self.find(|b| b.number == number)
+        }
+
+        async fn receipts_by_hash(&mut self, hash: B256) -> eyre::Result<Vec<Receipt>> {
+            self.find(|b| b.hash() == hash).map(|_| Vec::new())
+        }
+
+        async fn block_info_and_transactions_by_hash(
+            &mut self,
+            hash: B256,
+        ) -> eyre::Result<(BlockInfo, Vec<TxEnvelope>)> {
+            self.find(|b| b.hash() == hash).map(|info| (info, Vec::new()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layered_populates_cache_from_fallback() {
+        let blocks = chain((0, B256::ZERO), 4, 0xa);
+        let fallback = CountingProvider { blocks: blocks.clone(), calls: Default::default() };
+        let mut provider =
+            LayeredChainProvider::new(InMemoryChainProvider::with_capacity(16), fallback.clone());
+
+        let info = provider.block_info_by_number(2).await.unwrap();
+        assert_eq!(info.hash, blocks.blocks()[&2].hash());
+        assert_eq!(provider.block_info_by_number(2).await.unwrap(), info);
+        provider.block_info_and_transactions_by_hash(info.hash).await.unwrap();
+        provider.block_info_and_transactions_by_hash(info.hash).await.unwrap();
+        provider.receipts_by_hash(info.hash).await.unwrap();
+        provider.receipts_by_hash(info.hash).await.unwrap();
+
+        // Each piece of data is only fetched once from the fallback provider
+        assert_eq!(fallback.calls(), 3);
+        assert!(provider.block_info_by_number(5).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_layered_caches_receipts_without_transactions() {
+        let blocks = chain((0, B256::ZERO), 4, 0xa);
+        let fallback = CountingProvider { blocks: blocks.clone(), calls: Default::default() };
+        let mut provider =
+            LayeredChainProvider::new(InMemoryChainProvider::with_capacity(16), fallback.clone());
+
+        // The receipts are requested before the transactions of their block
+        let info = provider.block_info_by_number(2).await.unwrap();
+        provider.receipts_by_hash(info.hash).await.unwrap();
+        provider.receipts_by_hash(info.hash).await.unwrap();
+        assert_eq!(fallback.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_layered_serves_committed_blocks_from_memory() {
+        let blocks = chain((0, B256::ZERO), 4, 0xa);
+        let fallback = CountingProvider { blocks: blocks.clone(), calls: Default::default() };
+        let mut memory = InMemoryChainProvider::with_capacity(16);
+        memory.commit(blocks.clone());
+        let mut provider = LayeredChainProvider::new(memory.clone(), fallback.clone());
+
+        for block in blocks.blocks_iter() {
+            provider.header_by_hash(block.hash()).await.unwrap();
+            provider.block_info_by_number(block.number).await.unwrap();
+        }
+        assert_eq!(fallback.calls(), 0);
+
+        // Reverted blocks are fetched again from the fallback provider
+        memory.revert_to(2);
+        provider.block_info_by_number(3).await.unwrap();
+        assert_eq!(fallback.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_layered_does_not_index_blocks_fetched_by_hash() {
+        let canonical = chain((0, B256::ZERO), 4, 0xa);
+        let reorged = chain((0, B256::ZERO), 4, 0xb);
+        let fallback = CountingProvider { blocks: reorged.clone(), calls: Default::default() };
+        let mut memory = InMemoryChainProvider::with_capacity(16);
+        memory.commit(canonical.clone());
+        let mut provider = LayeredChainProvider::new(memory, fallback.clone());
+
+        // Fetch the reorged-out blocks by hash through the fallback provider
+        for block in reorged.blocks_iter() {
+            provider.header_by_hash(block.hash()).await.unwrap();
+            provider.block_info_and_transactions_by_hash(block.hash()).await.unwrap();
+        }
+        assert_eq!(fallback.calls(), 8);
+
+        // They are cached by hash, but the canonical blocks are still served by number
+        for (reorged, canonical) in reorged.blocks_iter().zip(canonical.blocks_iter()) {
+            provider.header_by_hash(reorged.hash()).await.unwrap();
+            provider.block_info_and_transactions_by_hash(reorged.hash()).await.unwrap();
+            let info = provider.block_info_by_number(canonical.number).await.unwrap();
+            assert_eq!(info.hash, canonical.hash());
+        }
+        assert_eq!(fallback.calls(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_evicts_oldest_blocks_fetched_by_hash() {
+        let blocks = chain((0, B256::ZERO), 4, 0xa);
+        let fallback = CountingProvider { blocks: blocks.clone(), calls: Default::default() };
+        let mut provider =
+            LayeredChainProvider::new(InMemoryChainProvider::with_capacity(2), fallback.clone());
+
+        for block in blocks.blocks_iter() {
+            provider.header_by_hash(block.hash()).await.unwrap();
+        }
+        assert_eq!(fallback.calls(), 4);
+
+        // Only the last two blocks are still cached
+        for block in blocks.blocks_iter().filter(|b| b.number > 2) {
+            provider.header_by_hash(block.hash()).await.unwrap();
+        }
+        assert_eq!(fallback.calls(), 4);
+        provider.header_by_hash(blocks.blocks()[&1].hash()).await.unwrap();
+        assert_eq!(fallback.calls(), 5);
+    }
+
+    /// Returns an arbitrary signed transaction of any type supported on L1.
+    fn arbitrary_tx(u: &mut Unstructured<'_>) -> arbitrary::Result<TxEnvelope> {
+        let sig =
+            Signature::from_rs_and_parity(u.arbitrary()?, u.arbitrary()?, u.arbitrary::<bool>()?)
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        Ok(match u.int_in_range(0..=4)? {
+            0 => {
+                let mut tx = u.arbitrary::<TxLegacy>()?;
+                // Keep the EIP-155 `v` value in range
+                tx.chain_id = tx.chain_id.map(|id| id as u32 as ChainId);
+                let sig = match tx.chain_id {
+                    Some(chain_id) => sig.with_chain_id(chain_id),
+                    None => sig.with_parity(Parity::NonEip155(sig.v().y_parity())),
+                };
+                tx.into_signed(sig).into()
+            }
+            1 => u.arbitrary::<TxEip2930>()?.into_signed(sig).into(),
+            2 => u.arbitrary::<TxEip1559>()?.into_signed(sig).into(),
+            3 => TxEip4844Variant::TxEip4844(u.arbitrary::<TxEip4844>()?).into_signed(sig).into(),
+            _ => u.arbitrary::<TxEip7702>()?.into_signed(sig).into(),
+        })
+    }
+
+    #[test]
+    fn roundtrip_reth_to_alloy_tx() {
+        arbtest::arbtest(|u| {
+            let tx = arbitrary_tx(u)?;
+            let encoded = tx.encoded_2718();
+            let reth_tx = TransactionSigned::decode_enveloped(&mut encoded.as_slice()).unwrap();
+
+            let converted = reth_to_alloy_tx(&reth_tx).unwrap();
+            assert_eq!(converted.tx_hash(), tx.tx_hash());
+            assert_eq!(converted.encoded_2718(), encoded);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_reth_to_alloy_tx_eip7702() {
+        let tx = TxEip7702 { chain_id: 1, nonce: 7, ..Default::default() };
+        let sig = Signature::from_rs_and_parity(U256::from(1), U256::from(2), true).unwrap();
+        let tx = TxEnvelope::from(tx.into_signed(sig));
+        let encoded = tx.encoded_2718();
+        let reth_tx = TransactionSigned::decode_enveloped(&mut encoded.as_slice()).unwrap();
+
+        let converted = reth_to_alloy_tx(&reth_tx).unwrap();
+        assert!(matches!(converted, TxEnvelope::Eip7702(_)));
+        assert_eq!(converted, tx);
+    }
+
+    #[cfg(feature = "optimism")]
+    #[test]
+    fn test_reth_to_alloy_tx_rejects_deposits() {
+        let tx = TransactionSigned::from_transaction_and_signature(
+            Transaction::Deposit(Default::default()),
+            reth::primitives::Signature::optimism_deposit_tx_signature(),
+        );
+        assert_eq!(reth_to_alloy_tx(&tx), Err(TxConversionError::UnsupportedTxType(0x7E)));
+    }
+
+    #[tokio::test]
+    async fn test_commit_typed_receipts() {
+        let log = Log::new_unchecked(
+            Address::with_last_byte(1),
+            vec![B256::with_last_byte(2)],
+            Bytes::new(),
+        );
+        let receipt = |tx_type, logs| {
+            Some(reth::primitives::Receipt {
+                tx_type,
+                success: true,
+                cumulative_gas_used: 21_000,
+                logs,
+                ..Default::default()
+            })
+        };
+        let receipts = vec![
+            vec![
+                receipt(RethTxType::Legacy, vec![]),
+                receipt(RethTxType::Eip7702, vec![log.clone()]),
+            ],
+            vec![None, receipt(RethTxType::Eip1559, vec![])],
+        ];
+        let outcome = ExecutionOutcome::new(Default::default(), receipts.into(), 1, Vec::new());
+        let blocks = chain((0, B256::ZERO), 2, 0xa);
+        let blocks = Chain::new(blocks.blocks_iter().cloned(), outcome, None);
+
+        let mut provider = InMemoryChainProvider::with_capacity(16);
+        provider.commit(Arc::new(blocks.clone()));
+
+        let hash = blocks.blocks()[&1].hash();
+        let envelopes = provider.receipt_envelopes_by_hash(hash).unwrap();
+        assert_eq!(envelopes.len(), 2);
+        assert!(matches!(envelopes[0], ReceiptEnvelope::Legacy(_)));
+        assert!(matches!(envelopes[1], ReceiptEnvelope::Eip7702(_)));
+        let mut bloom = Bloom::ZERO;
+        bloom.accrue_log(&log);
+        assert_eq!(envelopes[1].logs_bloom(), &bloom);
+        assert_eq!(provider.receipts_by_hash(hash).await.unwrap()[1].logs, vec![log]);
+
+        // Receipts are not cached if any of them is missing, as that would shift their indices
+        let hash = blocks.blocks()[&2].hash();
+        assert!(provider.receipts_by_hash(hash).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_evicts_lowest_blocks() {
+        let mut provider = InMemoryChainProvider::with_capacity(4);
+        let first = chain((0, B256::ZERO), 3, 0xa);
+        provider.commit(first.clone());
+        provider.commit(chain(tip(&first), 3, 0xa));
+
+        for number in 1..=2 {
+            assert!(provider.block_info_by_number(number).await.is_err());
+        }
+        for number in 3..=6 {
+            assert!(provider.block_info_by_number(number).await.is_ok());
+        }
+        let evicted = first.blocks().get(&1).unwrap().hash();
+        assert!(provider.header_by_hash(evicted).await.is_err());
+    }
 }
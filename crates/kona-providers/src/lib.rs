@@ -11,7 +11,7 @@ extern crate alloc;
 pub use kona_derive::traits::*;
 
 pub mod chain_provider;
-pub use chain_provider::InMemoryChainProvider;
+pub use chain_provider::{InMemoryChainProvider, LayeredChainProvider, RethChainProvider};
 
 pub mod blob_provider;
-pub use blob_provider::LayeredBlobProvider;
+pub use blob_provider::{DurableBlobProvider, LayeredBlobProvider};
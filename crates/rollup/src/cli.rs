@@ -1,7 +1,8 @@
 //! Module for the Hera Execution Extension CLI arguments.
 
-use std::{fs::File, path::PathBuf, sync::Arc};
+use std::{fs::File, net::SocketAddr, path::PathBuf, sync::Arc};
 
+use alloy::primitives::Address;
 use clap::Args;
 use eyre::{bail, Context, Result};
 use op_alloy_genesis::RollupConfig;
@@ -55,33 +56,107 @@ pub struct HeraArgsExt {
     #[clap(long = "hera.l1-blob-archiver-url")]
     pub l1_blob_archiver_url: Option<Url>,
 
+    /// Follow the L1 head and finality from the event stream of the L1 beacon client,
+    /// fetching the execution blocks by hash from the L1 execution client (over HTTP).
+    /// (This is only used when running in Standalone mode)
+    #[clap(long = "hera.l1-beacon-events")]
+    pub l1_beacon_events: bool,
+
     /// The payload validation mode to use.
     ///
     /// - Trusted: rely on a trusted synced L2 execution client. Validation happens by fetching the
     ///   same block and comparing the results.
     /// - Engine API: use a local or remote engine API of an L2 execution client. Validation
     ///   happens by sending the `new_payload` to the API and expecting a VALID response.
+    /// - Consensus: drive an L2 execution client through its engine API, like op-node does.
+    ///   Derived payloads are built and inserted with `forkchoiceUpdated`, `getPayload` and
+    ///   `newPayload`, advancing the heads of the execution client.
     #[clap(
         long = "hera.validation-mode",
         default_value = "trusted",
-        requires_ifs([("engine-api", "l2_engine_api_url"), ("engine-api", "l2_engine_jwt_secret")]),
+        requires_ifs([
+            ("engine-api", "l2_engine_api_url"),
+            ("engine-api", "l2_engine_jwt_secret"),
+            ("consensus", "l2_engine_api_url"),
+            ("consensus", "l2_engine_jwt_secret"),
+        ]),
     )]
     pub validation_mode: ValidationMode,
 
-    /// If the mode is "engine api", we also need an URL for the engine API endpoint of
-    /// the execution client to validate the payload.
+    /// If the mode is "engine api" or "consensus", we also need an URL for the engine API
+    /// endpoint of the execution client to validate or insert the payload.
     #[clap(long = "hera.l2-engine-api-url")]
     pub l2_engine_api_url: Option<Url>,
 
-    /// If the mode is "engine api", we also need a JWT secret for the auth-rpc.
+    /// If the mode is "engine api" or "consensus", we also need a JWT secret for the auth-rpc.
     /// This MUST be a valid path to a file containing the hex-encoded JWT secret.
     #[clap(long = "hera.l2-engine-jwt-secret")]
     pub l2_engine_jwt_secret: Option<PathBuf>,
 
+    /// The policy to apply when derived payload attributes fail validation.
+    ///
+    /// - Retry: log the error and validate the same attributes again on the next step.
+    /// - Halt: stop the driver and exit with a non-zero exit code.
+    /// - Skip: replace the invalid block with a deposit-only block, like op-node does, and
+    ///   continue.
+    /// - Reset: reset the derivation pipeline to the last safe L2 block, halting if the same block
+    ///   is still invalid after a few resets.
+    /// - Dump: write a diagnostic bundle to the dump directory, then skip the block.
+    #[clap(long = "hera.on-invalid-payload", default_value = "retry")]
+    pub on_invalid_payload: InvalidPayloadPolicy,
+
+    /// Directory where diagnostic bundles of invalid payloads are written
+    /// when using the "dump" invalid payload policy.
+    ///
+    /// Defaults to the `invalid-payloads` directory in the data directory.
+    #[clap(long = "hera.invalid-payload-dump-dir")]
+    pub invalid_payload_dump_dir: Option<PathBuf>,
+
+    /// The maximum number of attempts for L1 and L2 RPC calls made by the driver.
+    ///
+    /// Calls that fail with a transient error (timeouts, connection errors, rate limits)
+    /// are retried with a bounded exponential backoff until this limit is reached.
+    #[clap(long = "hera.rpc-max-attempts", default_value_t = 5)]
+    pub rpc_max_attempts: u32,
+
+    /// Directory where Hera persists its state, allowing the driver
+    /// to resume from its last checkpoint after a restart.
+    ///
+    /// If unset, no state is persisted and the driver always starts
+    /// from the latest L2 block.
+    #[clap(long = "hera.datadir")]
+    pub datadir: Option<PathBuf>,
+
+    /// RPC URL of a trusted rollup node to verify the output roots of derived blocks against,
+    /// using `optimism_outputAtBlock`.
+    #[clap(long = "hera.output-reference-rpc-url", conflicts_with = "l2_output_oracle")]
+    pub output_reference_rpc_url: Option<Url>,
+
+    /// Address of the `L2OutputOracle` contract on L1 to verify the output roots of derived
+    /// blocks against. Only the proposed L2 blocks can be verified this way.
+    #[clap(long = "hera.l2-output-oracle")]
+    pub l2_output_oracle: Option<Address>,
+
+    /// The L2 block to start deriving from: a block number, or one of
+    /// "genesis", "latest", "safe" or "finalized".
+    ///
+    /// If set, this takes precedence over the checkpoint persisted in the data directory.
+    /// Otherwise, the driver resumes from its checkpoint, or starts from the latest L2 block.
+    #[clap(long = "hera.start-block")]
+    pub start_block: Option<StartBlock>,
+
+    /// The address to serve the `optimism_*` JSON-RPC namespace on.
+    ///
+    /// If unset, the rollup node RPC server is disabled.
+    #[clap(long = "hera.rpc-addr")]
+    pub rpc_addr: Option<SocketAddr>,
+
     /// The maximum **number of blocks** to keep cached in the chain provider.
     ///
     /// This is used to limit the memory usage of the chain provider.
-    /// When the limit is reached, the oldest blocks are discarded.
+    /// When the limit is reached, the oldest blocks are discarded. Blocks missing
+    /// from the cache are read from the node's database in ExEx mode, and from
+    /// the L1 RPC in standalone mode.
     #[clap(long = "hera.l1-chain-cache-size", default_value_t = 256)]
     pub l1_chain_cache_size: usize,
 }
@@ -104,6 +179,14 @@ impl HeraArgsExt {
             }
         }
     }
+
+    /// Get the directory where diagnostic bundles of invalid payloads are written,
+    /// defaulting to the `invalid-payloads` directory in the data directory.
+    pub fn get_invalid_payload_dump_dir(&self) -> Option<PathBuf> {
+        self.invalid_payload_dump_dir
+            .clone()
+            .or_else(|| self.datadir.as_ref().map(|datadir| datadir.join("invalid-payloads")))
+    }
 }
 
 /// The payload validation mode.
@@ -117,12 +200,16 @@ impl HeraArgsExt {
 /// - Engine API: use the authenticated engine API of an L2 execution client. Validation happens by
 ///   sending the `new_payload` to the API and expecting a VALID response. This method can also be
 ///   used to verify unsafe payloads from the sequencer.
+/// - Consensus: act as the consensus client of an L2 execution client. Derived payloads are built
+///   and inserted through the engine API, so the execution client follows the derived chain.
 #[derive(Debug, Clone)]
 pub enum ValidationMode {
     /// Use a trusted synced L2 execution client.
     Trusted,
     /// Use the authenticated engine API of an L2 execution client.
     EngineApi,
+    /// Drive the authenticated engine API of an L2 execution client.
+    Consensus,
 }
 
 impl std::str::FromStr for ValidationMode {
@@ -132,6 +219,7 @@ impl std::str::FromStr for ValidationMode {
         match s.to_lowercase().as_str() {
             "trusted" => Ok(ValidationMode::Trusted),
             "engine-api" => Ok(ValidationMode::EngineApi),
+            "consensus" => Ok(ValidationMode::Consensus),
             _ => Err(format!("Invalid validation mode: {}", s)),
         }
     }
@@ -142,6 +230,128 @@ impl std::fmt::Display for ValidationMode {
         match self {
             ValidationMode::Trusted => write!(f, "trusted"),
             ValidationMode::EngineApi => write!(f, "engine-api"),
+            ValidationMode::Consensus => write!(f, "consensus"),
+        }
+    }
+}
+
+/// The policy to apply when derived payload attributes fail validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidPayloadPolicy {
+    /// Validate the same attributes again on the next step.
+    Retry,
+    /// Stop the driver with an error.
+    Halt,
+    /// Replace the invalid block with a deposit-only block and continue.
+    Skip,
+    /// Reset the derivation pipeline to the last safe L2 block.
+    Reset,
+    /// Write a diagnostic bundle, then replace the invalid block with a deposit-only block
+    /// and continue.
+    Dump,
+}
+
+impl std::str::FromStr for InvalidPayloadPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "retry" => Ok(InvalidPayloadPolicy::Retry),
+            "halt" => Ok(InvalidPayloadPolicy::Halt),
+            "skip" => Ok(InvalidPayloadPolicy::Skip),
+            "reset" => Ok(InvalidPayloadPolicy::Reset),
+            "dump" => Ok(InvalidPayloadPolicy::Dump),
+            _ => Err(format!("Invalid payload policy: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidPayloadPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidPayloadPolicy::Retry => write!(f, "retry"),
+            InvalidPayloadPolicy::Halt => write!(f, "halt"),
+            InvalidPayloadPolicy::Skip => write!(f, "skip"),
+            InvalidPayloadPolicy::Reset => write!(f, "reset"),
+            InvalidPayloadPolicy::Dump => write!(f, "dump"),
+        }
+    }
+}
+
+/// The L2 block to start deriving from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartBlock {
+    /// A specific L2 block number.
+    Number(u64),
+    /// The L2 genesis block of the rollup.
+    Genesis,
+    /// The latest L2 block known to the L2 execution client.
+    Latest,
+    /// The safe L2 block of the L2 execution client.
+    Safe,
+    /// The finalized L2 block of the L2 execution client.
+    Finalized,
+}
+
+impl std::str::FromStr for StartBlock {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "genesis" => Ok(StartBlock::Genesis),
+            "latest" => Ok(StartBlock::Latest),
+            "safe" => Ok(StartBlock::Safe),
+            "finalized" => Ok(StartBlock::Finalized),
+            number => number
+                .parse()
+                .map(StartBlock::Number)
+                .map_err(|_| format!("Invalid start block: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for StartBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StartBlock::Number(number) => write!(f, "{}", number),
+            StartBlock::Genesis => write!(f, "genesis"),
+            StartBlock::Latest => write!(f, "latest"),
+            StartBlock::Safe => write!(f, "safe"),
+            StartBlock::Finalized => write!(f, "finalized"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_start_block() {
+        assert_eq!("123".parse(), Ok(StartBlock::Number(123)));
+        assert_eq!("genesis".parse(), Ok(StartBlock::Genesis));
+        assert_eq!("Latest".parse(), Ok(StartBlock::Latest));
+        assert_eq!("safe".parse(), Ok(StartBlock::Safe));
+        assert_eq!("finalized".parse(), Ok(StartBlock::Finalized));
+        assert!("-1".parse::<StartBlock>().is_err());
+        assert!("pending".parse::<StartBlock>().is_err());
+
+        for start in [StartBlock::Number(7), StartBlock::Genesis, StartBlock::Finalized] {
+            assert_eq!(start.to_string().parse(), Ok(start));
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_payload_policy() {
+        for policy in [
+            InvalidPayloadPolicy::Retry,
+            InvalidPayloadPolicy::Halt,
+            InvalidPayloadPolicy::Skip,
+            InvalidPayloadPolicy::Reset,
+            InvalidPayloadPolicy::Dump,
+        ] {
+            assert_eq!(policy.to_string().parse(), Ok(policy));
         }
+        assert!("ignore".parse::<InvalidPayloadPolicy>().is_err());
     }
 }
@@ -0,0 +1,199 @@
+//! Persistent driver checkpoints.
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use eyre::{bail, Context, Result};
+use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+/// The name of the checkpoint file inside the data directory.
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+/// The name of the temporary file used to atomically replace the checkpoint.
+const CHECKPOINT_TMP_FILE: &str = "checkpoint.json.tmp";
+
+/// The current version of the checkpoint format.
+const CHECKPOINT_VERSION: u64 = 1;
+
+/// A snapshot of the driver state that allows it to resume after a restart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The version of the checkpoint format.
+    pub version: u64,
+    /// The cursor entries: each L1 origin block with its corresponding tip L2 block,
+    /// ordered by L1 origin block number.
+    pub l1_origin_to_l2_tip: Vec<(BlockInfo, L2BlockInfo)>,
+    /// The safe L2 head.
+    pub safe_head: L2BlockInfo,
+    /// The finalized L2 head.
+    pub finalized_head: L2BlockInfo,
+    /// The last L1 block number reported as processed to the driver context.
+    pub last_processed_l1: u64,
+}
+
+impl Checkpoint {
+    /// Creates a new [Checkpoint] with the current format version.
+    pub const fn new(
+        l1_origin_to_l2_tip: Vec<(BlockInfo, L2BlockInfo)>,
+        safe_head: L2BlockInfo,
+        finalized_head: L2BlockInfo,
+        last_processed_l1: u64,
+    ) -> Self {
+        Self {
+            version: CHECKPOINT_VERSION,
+            l1_origin_to_l2_tip,
+            safe_head,
+            finalized_head,
+            last_processed_l1,
+        }
+    }
+}
+
+/// A small on-disk store for driver [Checkpoint]s.
+///
+/// The checkpoint is stored as a single JSON file in the data directory. Writes are
+/// crash-consistent: the new checkpoint is written and synced to a temporary file first,
+/// which then atomically replaces the previous one. A crash at any point leaves either
+/// the previous or the new checkpoint on disk, never a partially written one.
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+    /// The data directory where the checkpoint is stored.
+    datadir: PathBuf,
+}
+
+impl CheckpointStore {
+    /// Opens a checkpoint store in the given data directory, creating it if needed.
+    pub fn open(datadir: impl AsRef<Path>) -> Result<Self> {
+        let datadir = datadir.as_ref().to_path_buf();
+        fs::create_dir_all(&datadir).wrap_err("Failed to create data directory")?;
+
+        // A leftover temporary file means that we crashed while writing a checkpoint,
+        // before it replaced the previous one. It can be safely discarded.
+        let tmp = datadir.join(CHECKPOINT_TMP_FILE);
+        if tmp.exists() {
+            warn!("Discarding incomplete checkpoint at {:?}", tmp);
+            fs::remove_file(&tmp).wrap_err("Failed to remove incomplete checkpoint")?;
+        }
+
+        Ok(Self { datadir })
+    }
+
+    /// Loads the last persisted checkpoint, if any.
+    pub fn load(&self) -> Result<Option<Checkpoint>> {
+        let path = self.datadir.join(CHECKPOINT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(&path).wrap_err("Failed to read checkpoint")?;
+        let checkpoint: Checkpoint =
+            serde_json::from_slice(&bytes).wrap_err("Failed to decode checkpoint")?;
+        if checkpoint.version != CHECKPOINT_VERSION {
+            bail!("Unsupported checkpoint version: {}", checkpoint.version);
+        }
+
+        Ok(Some(checkpoint))
+    }
+
+    /// Atomically persists the given checkpoint, replacing the previous one.
+    pub fn save(&self, checkpoint: &Checkpoint) -> Result<()> {
+        let tmp = self.datadir.join(CHECKPOINT_TMP_FILE);
+        let path = self.datadir.join(CHECKPOINT_FILE);
+
+        let mut file = File::create(&tmp).wrap_err("Failed to create checkpoint file")?;
+        file.write_all(&serde_json::to_vec(checkpoint)?).wrap_err("Failed to write checkpoint")?;
+        file.sync_all().wrap_err("Failed to sync checkpoint")?;
+        drop(file);
+
+        fs::rename(&tmp, &path).wrap_err("Failed to replace checkpoint")?;
+
+        // Sync the directory so that the rename itself is durable.
+        File::open(&self.datadir).and_then(|dir| dir.sync_all()).ok();
+
+        debug!(safe_head = checkpoint.safe_head.block_info.number, "Persisted driver checkpoint");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datadir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("hera-checkpoint-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn checkpoint(safe: u64) -> Checkpoint {
+        let entries = (1..=safe)
+            .map(|i| {
+                let mut l2 = L2BlockInfo::default();
+                l2.block_info.number = 100 + i;
+                l2.l1_origin.number = i;
+                (BlockInfo { number: i, ..Default::default() }, l2)
+            })
+            .collect::<Vec<_>>();
+        let safe_head = entries.last().map(|(_, l2)| *l2).unwrap_or_default();
+        Checkpoint::new(entries, safe_head, L2BlockInfo::default(), safe)
+    }
+
+    #[test]
+    fn test_empty_store() {
+        let dir = datadir("empty");
+        let store = CheckpointStore::open(&dir).unwrap();
+        assert_eq!(store.load().unwrap(), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = datadir("roundtrip");
+        let store = CheckpointStore::open(&dir).unwrap();
+
+        store.save(&checkpoint(3)).unwrap();
+        assert_eq!(store.load().unwrap(), Some(checkpoint(3)));
+
+        // A new checkpoint replaces the previous one, including after reopening the store.
+        store.save(&checkpoint(5)).unwrap();
+        let store = CheckpointStore::open(&dir).unwrap();
+        assert_eq!(store.load().unwrap(), Some(checkpoint(5)));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_crash_during_write_keeps_previous_checkpoint() {
+        let dir = datadir("crash");
+        let store = CheckpointStore::open(&dir).unwrap();
+        store.save(&checkpoint(3)).unwrap();
+
+        // Simulate a crash in the middle of writing the next checkpoint:
+        // the temporary file is truncated and never renamed.
+        let partial = serde_json::to_vec(&checkpoint(4)).unwrap();
+        fs::write(dir.join(CHECKPOINT_TMP_FILE), &partial[..partial.len() / 2]).unwrap();
+
+        let store = CheckpointStore::open(&dir).unwrap();
+        assert!(!dir.join(CHECKPOINT_TMP_FILE).exists());
+        assert_eq!(store.load().unwrap(), Some(checkpoint(3)));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_checkpoint_is_an_error() {
+        let dir = datadir("corrupted");
+        let store = CheckpointStore::open(&dir).unwrap();
+        fs::write(dir.join(CHECKPOINT_FILE), b"{\"version\":").unwrap();
+
+        assert!(store.load().is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+//! L1 head and finality events from the event stream of a beacon node.
+
+use std::collections::VecDeque;
+
+use alloy::{eips::BlockNumHash, primitives::B256};
+use eyre::{eyre, Result};
+use reqwest::header::ACCEPT;
+use serde::Deserialize;
+use tracing::warn;
+use url::Url;
+
+/// The beacon API event topics to subscribe to.
+const EVENT_TOPICS: &str = "head,finalized_checkpoint,chain_reorg";
+
+/// An event received from the event stream of a beacon node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BeaconEvent {
+    /// A new beacon block became the head of the chain.
+    Head { block: B256 },
+    /// A new checkpoint was finalized.
+    FinalizedCheckpoint { block: B256 },
+    /// The beacon chain was reorganized with the given head block.
+    ChainReorg { depth: u64, new_head_block: B256 },
+}
+
+impl BeaconEvent {
+    /// Parse a beacon event from the event type and data of a server-sent event.
+    ///
+    /// Returns `None` for event types that are not subscribed to.
+    fn parse(event: &str, data: &str) -> Result<Option<Self>> {
+        Ok(Some(match event {
+            "head" => Self::Head { block: serde_json::from_str::<BlockEvent>(data)?.block },
+            "finalized_checkpoint" => {
+                Self::FinalizedCheckpoint { block: serde_json::from_str::<BlockEvent>(data)?.block }
+            }
+            "chain_reorg" => {
+                let reorg = serde_json::from_str::<ChainReorgEvent>(data)?;
+                Self::ChainReorg {
+                    depth: reorg.depth.parse()?,
+                    new_head_block: reorg.new_head_block,
+                }
+            }
+            _ => return Ok(None),
+        }))
+    }
+}
+
+/// The data of the `head` and `finalized_checkpoint` events.
+#[derive(Debug, Deserialize)]
+struct BlockEvent {
+    block: B256,
+}
+
+/// The data of the `chain_reorg` event.
+#[derive(Debug, Deserialize)]
+struct ChainReorgEvent {
+    depth: String,
+    new_head_block: B256,
+}
+
+/// The response of the `blinded_blocks` endpoint, reduced to the execution payload header.
+#[derive(Debug, Deserialize)]
+struct BlindedBlockResponse {
+    data: SignedBlindedBlock,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignedBlindedBlock {
+    message: BlindedBlock,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlindedBlock {
+    body: BlindedBlockBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlindedBlockBody {
+    execution_payload_header: ExecutionPayloadHeader,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExecutionPayloadHeader {
+    block_number: String,
+    block_hash: B256,
+}
+
+/// A client for the event stream of a beacon node.
+#[derive(Debug, Clone)]
+pub(crate) struct BeaconEventClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl BeaconEventClient {
+    /// Create a new client for the beacon node at the given URL.
+    pub(crate) fn new(beacon_url: &Url) -> Self {
+        let base_url = beacon_url.as_str().trim_end_matches('/').to_string();
+        Self { client: reqwest::Client::new(), base_url }
+    }
+
+    /// Subscribe to the head, finality and reorg events of the beacon node.
+    pub(crate) async fn subscribe(&self) -> Result<BeaconEventStream> {
+        let url = format!("{}/eth/v1/events?topics={}", self.base_url, EVENT_TOPICS);
+        let response = self
+            .client
+            .get(url)
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(BeaconEventStream { response, parser: SseParser::default(), events: VecDeque::new() })
+    }
+
+    /// Returns the number and hash of the execution block of the given beacon block.
+    pub(crate) async fn execution_block(&self, block_root: B256) -> Result<BlockNumHash> {
+        let url = format!("{}/eth/v1/beacon/blinded_blocks/{}", self.base_url, block_root);
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        let header = response.json::<BlindedBlockResponse>().await?.data.message.body;
+        let header = header.execution_payload_header;
+
+        let number = header
+            .block_number
+            .parse()
+            .map_err(|e| eyre!("Invalid execution block number {}: {}", header.block_number, e))?;
+        Ok(BlockNumHash { number, hash: header.block_hash })
+    }
+}
+
+/// A stream of events from a beacon node.
+#[derive(Debug)]
+pub(crate) struct BeaconEventStream {
+    response: reqwest::Response,
+    parser: SseParser,
+    events: VecDeque<BeaconEvent>,
+}
+
+impl BeaconEventStream {
+    /// Returns the next event of the stream, or `None` if the stream was closed.
+    pub(crate) async fn next(&mut self) -> Result<Option<BeaconEvent>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let Some(chunk) = self.response.chunk().await? else {
+                return Ok(None);
+            };
+            for (event, data) in self.parser.feed(&chunk) {
+                match BeaconEvent::parse(&event, &data) {
+                    Ok(Some(event)) => self.events.push_back(event),
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to parse beacon event {}: {:?}", event, e),
+                }
+            }
+        }
+    }
+}
+
+/// An incremental parser for the `text/event-stream` format.
+#[derive(Debug, Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: String,
+}
+
+impl SseParser {
+    /// Feed a chunk of the stream, returning the `(event, data)` pairs that were completed.
+    fn feed(&mut self, chunk: &[u8]) -> Vec<(String, String)> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line = self.buffer.drain(..=pos).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                // An empty line dispatches the event
+                if !self.data.is_empty() {
+                    events.push((std::mem::take(&mut self.event), std::mem::take(&mut self.data)));
+                }
+                self.event.clear();
+            } else if let Some(event) = line.strip_prefix("event:") {
+                self.event = event.trim_start().to_string();
+            } else if let Some(data) = line.strip_prefix("data:") {
+                if !self.data.is_empty() {
+                    self.data.push('\n');
+                }
+                self.data.push_str(data.trim_start());
+            }
+            // Comments and other fields are ignored
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(b": keep-alive\n\nevent: he").is_empty());
+        assert!(parser.feed(b"ad\r\ndata: {\"block\":").is_empty());
+
+        let events = parser.feed(b" 1}\r\n\r\nevent: finalized_checkpoint\ndata: 2\n\n");
+        assert_eq!(
+            events,
+            vec![
+                ("head".to_string(), "{\"block\": 1}".to_string()),
+                ("finalized_checkpoint".to_string(), "2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_beacon_events() {
+        let block = B256::with_last_byte(1);
+        let head = format!(r#"{{"slot":"10","block":"{block}","state":"{block}"}}"#);
+        assert_eq!(BeaconEvent::parse("head", &head).unwrap(), Some(BeaconEvent::Head { block }));
+
+        let finalized = format!(r#"{{"block":"{block}","state":"{block}","epoch":"2"}}"#);
+        assert_eq!(
+            BeaconEvent::parse("finalized_checkpoint", &finalized).unwrap(),
+            Some(BeaconEvent::FinalizedCheckpoint { block })
+        );
+
+        let reorg = format!(
+            r#"{{"slot":"10","depth":"3","old_head_block":"{}","new_head_block":"{block}"}}"#,
+            B256::ZERO
+        );
+        assert_eq!(
+            BeaconEvent::parse("chain_reorg", &reorg).unwrap(),
+            Some(BeaconEvent::ChainReorg { depth: 3, new_head_block: block })
+        );
+
+        assert_eq!(BeaconEvent::parse("block", "{}").unwrap(), None);
+        assert!(BeaconEvent::parse("head", "{}").is_err());
+    }
+}
@@ -1,10 +1,14 @@
 use alloy::primitives::BlockNumber;
 use async_trait::async_trait;
 use futures::StreamExt;
-use kona_providers::InMemoryChainProvider;
+use kona_providers::{InMemoryChainProvider, LayeredBlobProvider};
+use metrics::counter;
+use reth::{providers::BlockIdReader, transaction_pool::TransactionPool};
+use reth_execution_types::Chain;
 use reth_exex::{ExExContext, ExExEvent};
 use reth_node_api::FullNodeComponents;
 use tokio::sync::mpsc::error::SendError;
+use tracing::{debug, warn};
 
 use crate::driver::{ChainNotification, DriverContext};
 
@@ -13,27 +17,96 @@ use crate::driver::{ChainNotification, DriverContext};
 /// This context is used to bridge the gap between the execution extension
 /// and the Hera pipeline. It receives notifications from the execution extension
 /// and forwards them to the Hera pipeline. It also maintains a shared L1 cache of
-/// the chain to make it available to the rollup pipeline.
+/// the chain and of the blob sidecars available in the node's blob store, to make
+/// them available to the rollup pipeline.
 pub struct ExExHeraContext<N: FullNodeComponents> {
     ctx: ExExContext<N>,
     l1_cache: InMemoryChainProvider,
+    blob_cache: LayeredBlobProvider,
+    /// The last finalized L1 block number that was notified to the driver.
+    l1_finalized: BlockNumber,
 }
 
 impl<N: FullNodeComponents> ExExHeraContext<N> {
     /// Create a new execution extension Hera context with the given
-    /// execution context and L1 cache.
-    pub fn new(ctx: ExExContext<N>, l1_cache: InMemoryChainProvider) -> Self {
-        Self { ctx, l1_cache }
+    /// execution context, L1 cache and blob cache.
+    pub fn new(
+        ctx: ExExContext<N>,
+        l1_cache: InMemoryChainProvider,
+        blob_cache: LayeredBlobProvider,
+    ) -> Self {
+        Self { ctx, l1_cache, blob_cache, l1_finalized: 0 }
+    }
+
+    /// Insert the blob sidecars of the blob transactions in the given chain into
+    /// the in-memory blob cache, using the node's blob store.
+    ///
+    /// Sidecars that are not available locally are fetched from the beacon node
+    /// by the blob provider when needed.
+    fn cache_blob_sidecars(&mut self, chain: &Chain) {
+        for (_, block) in chain.blocks() {
+            let mut sidecars = Vec::new();
+            for tx in block.transactions().filter(|tx| tx.is_eip4844()) {
+                match self.ctx.pool().get_blob(tx.hash()) {
+                    Ok(Some(sidecar)) => {
+                        counter!("hera_exex_blob_sidecars_total", "status" => "cached")
+                            .increment(1);
+                        sidecars.push(sidecar);
+                    }
+                    Ok(None) => {
+                        counter!("hera_exex_blob_sidecars_total", "status" => "missing")
+                            .increment(1);
+                    }
+                    Err(err) => {
+                        counter!("hera_exex_blob_sidecars_total", "status" => "error").increment(1);
+                        warn!("Failed to read blob sidecar of tx {}: {:?}", tx.hash(), err);
+                    }
+                }
+            }
+
+            if !sidecars.is_empty() {
+                debug!("Caching {} blob sidecars for L1 block {}", sidecars.len(), block.number);
+                self.blob_cache.insert_blob_sidecars(block.hash(), sidecars);
+            }
+        }
+    }
+
+    /// Returns the finalized L1 block number from the node, if it moved
+    /// forward since the last time it was checked.
+    fn poll_finalized_block(&mut self) -> Option<BlockNumber> {
+        match self.ctx.provider().finalized_block_number() {
+            Ok(Some(finalized)) if finalized > self.l1_finalized => {
+                self.l1_finalized = finalized;
+                Some(finalized)
+            }
+            Ok(_) => None,
+            Err(err) => {
+                warn!("Failed to read the finalized L1 block: {:?}", err);
+                None
+            }
+        }
     }
 }
 
 #[async_trait]
 impl<N: FullNodeComponents> DriverContext for ExExHeraContext<N> {
     async fn recv_notification(&mut self) -> Option<ChainNotification> {
+        // Finality updates are not part of the ExEx notifications, so we check
+        // the node's forkchoice state before waiting for the next notification.
+        if let Some(l1_block) = self.poll_finalized_block() {
+            return Some(ChainNotification::Finalized { l1_block });
+        }
+
         let exex_notification = self.ctx.notifications.next().await?;
 
-        // Commit the new chain to the L1 cache to make it available to the pipeline
+        // Remove the reverted chain from the L1 cache so that its blocks are never served
+        if let Some(chain) = exex_notification.reverted_chain() {
+            self.l1_cache.revert(chain);
+        }
+
+        // Commit the new chain to the L1 and blob caches to make it available to the pipeline
         if let Some(chain) = exex_notification.committed_chain() {
+            self.cache_blob_sidecars(&chain);
             self.l1_cache.commit(chain);
         }
 
@@ -0,0 +1,51 @@
+//! A scripted [DriverContext] for driver tests.
+
+use std::collections::VecDeque;
+
+use alloy::primitives::BlockNumber;
+use async_trait::async_trait;
+use tokio::sync::mpsc::error::SendError;
+
+use super::{ChainNotification, DriverContext};
+
+/// A [DriverContext] that replays a scripted sequence of [ChainNotification]s,
+/// and records the processed tips sent back by the driver.
+///
+/// Once all notifications have been delivered, [DriverContext::recv_notification]
+/// returns `None`.
+#[derive(Debug, Default)]
+pub struct MockDriverContext {
+    /// The notifications left to deliver, in order.
+    notifications: VecDeque<ChainNotification>,
+    /// The processed tips sent by the driver, in order.
+    processed_tips: Vec<BlockNumber>,
+}
+
+impl MockDriverContext {
+    /// Creates a new [MockDriverContext] delivering the given notifications.
+    pub fn new(notifications: impl IntoIterator<Item = ChainNotification>) -> Self {
+        Self { notifications: notifications.into_iter().collect(), processed_tips: Vec::new() }
+    }
+
+    /// Schedules a notification to be delivered after the pending ones.
+    pub fn push(&mut self, notification: ChainNotification) {
+        self.notifications.push_back(notification);
+    }
+
+    /// Returns the processed tips sent by the driver so far.
+    pub fn processed_tips(&self) -> &[BlockNumber] {
+        &self.processed_tips
+    }
+}
+
+#[async_trait]
+impl DriverContext for MockDriverContext {
+    async fn recv_notification(&mut self) -> Option<ChainNotification> {
+        self.notifications.pop_front()
+    }
+
+    fn send_processed_tip_event(&mut self, tip: BlockNumber) -> Result<(), SendError<BlockNumber>> {
+        self.processed_tips.push(tip);
+        Ok(())
+    }
+}
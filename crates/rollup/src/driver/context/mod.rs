@@ -13,13 +13,21 @@ use reth::rpc::types::{BlockTransactions, Header};
 use reth_execution_types::Chain;
 use reth_exex::ExExNotification;
 use tokio::sync::mpsc::error::SendError;
+use tracing::error;
 
 mod exex;
 pub use exex::ExExHeraContext;
 
+mod beacon;
+
 mod standalone;
 pub use standalone::StandaloneHeraContext;
 
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+pub use mock::MockDriverContext;
+
 /// Context for the rollup driver.
 ///
 /// The context is responsible for handling notifications from the state of the
@@ -43,6 +51,8 @@ pub enum ChainNotification {
     Revert { old_blocks: Blocks },
     /// The chain has been reorganized with new canonical blocks.
     Reorg { old_blocks: Blocks, new_blocks: Blocks },
+    /// The given L1 block (and all its ancestors) has been finalized.
+    Finalized { l1_block: BlockNumber },
 }
 
 impl ChainNotification {
@@ -53,7 +63,7 @@ impl ChainNotification {
         match self {
             ChainNotification::New { new_blocks } => Some(new_blocks.clone()),
             ChainNotification::Reorg { new_blocks, .. } => Some(new_blocks.clone()),
-            ChainNotification::Revert { .. } => None,
+            ChainNotification::Revert { .. } | ChainNotification::Finalized { .. } => None,
         }
     }
 
@@ -64,7 +74,15 @@ impl ChainNotification {
         match self {
             ChainNotification::Revert { old_blocks } => Some(old_blocks.clone()),
             ChainNotification::Reorg { old_blocks, .. } => Some(old_blocks.clone()),
-            ChainNotification::New { .. } => None,
+            ChainNotification::New { .. } | ChainNotification::Finalized { .. } => None,
+        }
+    }
+
+    /// Returns the finalized L1 block number contained in the notification event, if any.
+    pub const fn finalized_block(&self) -> Option<BlockNumber> {
+        match self {
+            ChainNotification::Finalized { l1_block } => Some(*l1_block),
+            _ => None,
         }
     }
 }
@@ -108,13 +126,28 @@ impl From<Arc<Chain>> for Blocks {
     fn from(value: Arc<Chain>) -> Self {
         let mut blocks = BTreeMap::new();
         for (block_number, sealed_block) in value.blocks() {
+            // The transactions of a block are only included if all of them can be converted,
+            // as they are looked up by index. Otherwise, only their hashes are kept.
+            let transactions =
+                match sealed_block.transactions().map(reth_to_alloy_tx).collect::<Result<_, _>>() {
+                    Ok(txs) => BlockTransactions::Full(txs),
+                    Err(e) => {
+                        error!(
+                            "Failed to convert the transactions of block {}: {}",
+                            sealed_block.hash(),
+                            e
+                        );
+                        BlockTransactions::Hashes(
+                            sealed_block.transactions().map(|tx| tx.hash).collect(),
+                        )
+                    }
+                };
+
             // from reth::primitives::SealedBlock to alloy::rpc::types::Block
             let block = Block {
                 header: parse_reth_rpc_header(sealed_block),
                 uncles: sealed_block.ommers.iter().map(|x| x.hash_slow()).collect(),
-                transactions: BlockTransactions::Full(
-                    sealed_block.transactions().flat_map(reth_to_alloy_tx).collect(),
-                ),
+                transactions,
                 size: Some(U256::from(sealed_block.size())),
                 withdrawals: sealed_block.withdrawals.clone().map(|w| w.into_inner()),
             };
@@ -1,30 +1,273 @@
 use hashbrown::HashMap;
-use std::{collections::BTreeMap, time::Duration};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Debug,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
 
 use alloy::{
     consensus::TxEnvelope,
     eips::BlockId,
     network::Ethereum,
     primitives::{BlockNumber, B256},
-    providers::{IpcConnect, Provider, ProviderBuilder, ReqwestProvider, WsConnect},
+    providers::{IpcConnect, Provider, ProviderBuilder, ReqwestProvider, RootProvider, WsConnect},
+    pubsub::{PubSubFrontend, SubscriptionStream},
     rpc::types::Block,
-    transports::{TransportErrorKind, TransportResult},
+    transports::{BoxTransport, TransportError, TransportErrorKind, TransportResult},
 };
 use async_trait::async_trait;
 use futures::StreamExt;
+use metrics::{counter, gauge};
 use reth::rpc::types::BlockTransactions;
 use tokio::{
-    sync::mpsc::{self, error::SendError},
+    sync::{
+        mpsc::{self, error::SendError},
+        watch,
+    },
     task::JoinHandle,
+    time::Instant,
 };
-use tracing::{debug, error, warn};
+use tracing::{debug, error, info, warn};
 use url::Url;
 
-use super::{Blocks, ChainNotification, DriverContext};
+use super::{
+    beacon::{BeaconEvent, BeaconEventClient},
+    Blocks, ChainNotification, DriverContext,
+};
+use crate::RetryPolicy;
+use kona_providers::InMemoryChainProvider;
+
+/// The maximum number of missing blocks to backfill below a new block, which bounds
+/// the number of blocks held in memory until they are linked to the tracked chain.
+/// Roughly 50 minutes of L1 blocks at 12 second slots. Deeper gaps re-anchor the
+/// tracked chain on the backfilled blocks instead.
+const MAX_BACKFILL_DEPTH: u64 = 256;
+
+/// The backoff policy used to retry a backfill that could not fetch a missing block.
+/// The queued blocks are dropped once all attempts failed, and backfilled again below
+/// the next received block.
+const BACKFILL_RETRY_POLICY: RetryPolicy =
+    RetryPolicy::new(5, Duration::from_millis(250), Duration::from_secs(5));
+
+/// The minimum interval between two polls of the finalized L1 block from the L1 node.
+/// Finality only advances once per epoch, so polling once per slot is enough.
+const FINALIZED_POLL_INTERVAL: Duration = Duration::from_secs(12);
+
+/// The backoff policy used to reconnect the L1 block subscription.
+/// Attempts are unbounded, as the context is useless while disconnected.
+const RECONNECT_POLICY: RetryPolicy =
+    RetryPolicy::new(u32::MAX, Duration::from_secs(1), Duration::from_secs(30));
+
+/// Fetches L1 blocks that were missed by the new block listener.
+#[async_trait]
+trait L1BlockFetcher: Debug + Send + Sync {
+    /// Fetch the full L1 block with the given hash.
+    async fn block_by_hash(&self, hash: B256) -> TransportResult<Option<Block<TxEnvelope>>>;
+
+    /// Fetch the number of the latest finalized L1 block.
+    async fn finalized_block_number(&self) -> TransportResult<Option<BlockNumber>>;
+}
+
+#[async_trait]
+impl L1BlockFetcher for RootProvider<BoxTransport> {
+    async fn block_by_hash(&self, hash: B256) -> TransportResult<Option<Block<TxEnvelope>>> {
+        Ok(self.get_block_by_hash(hash, true.into()).await?.map(parse_reth_rpc_block))
+    }
+
+    async fn finalized_block_number(&self) -> TransportResult<Option<BlockNumber>> {
+        Ok(self.get_block(BlockId::finalized(), false.into()).await?.map(|b| b.header.number))
+    }
+}
+
+/// A [L1BlockFetcher] whose client is replaced every time the L1 subscription reconnects.
+#[derive(Debug, Clone)]
+struct ReconnectingFetcher(Arc<RwLock<RootProvider<BoxTransport>>>);
+
+impl ReconnectingFetcher {
+    fn new(client: RootProvider<BoxTransport>) -> Self {
+        Self(Arc::new(RwLock::new(client)))
+    }
+
+    fn replace(&self, client: RootProvider<BoxTransport>) {
+        *self.0.write().expect("lock poisoned") = client;
+    }
+
+    fn client(&self) -> RootProvider<BoxTransport> {
+        self.0.read().expect("lock poisoned").clone()
+    }
+}
+
+#[async_trait]
+impl L1BlockFetcher for ReconnectingFetcher {
+    async fn block_by_hash(&self, hash: B256) -> TransportResult<Option<Block<TxEnvelope>>> {
+        self.client().block_by_hash(hash).await
+    }
+
+    async fn finalized_block_number(&self) -> TransportResult<Option<BlockNumber>> {
+        self.client().finalized_block_number().await
+    }
+}
+
+/// An L1 node endpoint that supports block subscriptions.
+#[derive(Debug, Clone)]
+enum PubSubEndpoint {
+    /// A websocket endpoint
+    Ws(Url),
+    /// An IPC socket path
+    Ipc(PathBuf),
+}
+
+impl PubSubEndpoint {
+    /// Connect to the endpoint and subscribe to new blocks.
+    async fn subscribe(
+        &self,
+    ) -> TransportResult<(RootProvider<PubSubFrontend>, SubscriptionStream<Block>)> {
+        let client = match self {
+            Self::Ws(url) => ProviderBuilder::new().on_ws(WsConnect::new(url.clone())).await?,
+            Self::Ipc(path) => ProviderBuilder::new().on_ipc(IpcConnect::new(path.clone())).await?,
+        };
+        let block_sub = client.subscribe_blocks().await?.into_stream();
+        Ok((client, block_sub))
+    }
+
+    /// Reconnect to the endpoint with backoff until the subscription succeeds,
+    /// replacing the client of the given fetcher.
+    async fn resubscribe(&self, fetcher: &ReconnectingFetcher) -> SubscriptionStream<Block> {
+        let mut attempt = 0;
+        loop {
+            tokio::time::sleep(RECONNECT_POLICY.backoff(attempt)).await;
+            counter!("hera_l1_reconnects_total").increment(1);
+            match self.subscribe().await {
+                Ok((client, block_sub)) => {
+                    info!("Reconnected to the L1 node after {} attempts", attempt + 1);
+                    fetcher.replace(client.boxed());
+                    return block_sub;
+                }
+                Err(e) => {
+                    warn!("Failed to reconnect to the L1 node (attempt {}): {:?}", attempt + 1, e);
+                    attempt = attempt.saturating_add(1);
+                }
+            }
+        }
+    }
+}
+
+/// The reason why the queued blocks could not be linked to the tracked chain.
+#[derive(Debug)]
+enum BackfillError {
+    /// More than [MAX_BACKFILL_DEPTH] blocks are missing.
+    TooDeep,
+    /// The missing block with the given hash was not found by the L1 node.
+    NotFound(B256),
+    /// The missing block with the given hash could not be fetched.
+    Transport(B256, TransportError),
+}
+
+impl std::fmt::Display for BackfillError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooDeep => write!(f, "more than {} blocks are missing", MAX_BACKFILL_DEPTH),
+            Self::NotFound(hash) => write!(f, "block {} not found", hash),
+            Self::Transport(hash, err) => write!(f, "failed to fetch block {}: {}", hash, err),
+        }
+    }
+}
+
+/// Record the connection state of the L1 subscription.
+fn set_connected(connected: &AtomicBool, value: bool) {
+    connected.store(value, Ordering::Relaxed);
+    gauge!("hera_l1_subscription_connected").set(if value { 1.0 } else { 0.0 });
+}
+
+/// Follow the event stream of a beacon node, sending the execution blocks of the new
+/// beacon heads and the finalized execution block numbers to the context.
+async fn follow_beacon_events<F: L1BlockFetcher>(
+    beacon: BeaconEventClient,
+    fetcher: F,
+    new_block_tx: mpsc::Sender<Block<TxEnvelope>>,
+    finalized_tx: watch::Sender<BlockNumber>,
+    connected: Arc<AtomicBool>,
+) {
+    let mut attempt = 0;
+    loop {
+        match beacon.subscribe().await {
+            Ok(mut events) => {
+                debug!("Subscribed to the beacon node event stream");
+                set_connected(&connected, true);
+                attempt = 0;
+
+                loop {
+                    let block_root = match events.next().await {
+                        Ok(Some(BeaconEvent::Head { block })) => block,
+                        Ok(Some(BeaconEvent::ChainReorg { depth, new_head_block })) => {
+                            info!("Beacon chain reorg of depth {} to {}", depth, new_head_block);
+                            new_head_block
+                        }
+                        Ok(Some(BeaconEvent::FinalizedCheckpoint { block })) => {
+                            match beacon.execution_block(block).await {
+                                Ok(finalized) => {
+                                    finalized_tx.send_if_modified(|current| {
+                                        let advanced = finalized.number > *current;
+                                        if advanced {
+                                            *current = finalized.number;
+                                        }
+                                        advanced
+                                    });
+                                }
+                                Err(e) => {
+                                    warn!("Failed to resolve finalized block {}: {:?}", block, e)
+                                }
+                            }
+                            continue;
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            warn!("Failed to read the beacon node event stream: {:?}", e);
+                            break;
+                        }
+                    };
+
+                    // Missed heads are backfilled by the context, so failures are only logged
+                    let head = match beacon.execution_block(block_root).await {
+                        Ok(head) => head,
+                        Err(e) => {
+                            warn!("Failed to resolve head block {}: {:?}", block_root, e);
+                            continue;
+                        }
+                    };
+                    match fetcher.block_by_hash(head.hash).await {
+                        Ok(Some(block)) => {
+                            if new_block_tx.send(block).await.is_err() {
+                                error!("New block channel closed, stopping the L1 listener");
+                                return;
+                            }
+                        }
+                        other => error!("Failed to get L1 block {}: {:?}", head.hash, other),
+                    }
+                }
+
+                warn!("Beacon node event stream closed, reconnecting");
+                set_connected(&connected, false);
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to subscribe to beacon node events (attempt {}): {:?}",
+                    attempt + 1,
+                    e
+                )
+            }
+        }
 
-/// The number of blocks to keep in the reorg cache.
-/// Equivalent to 2 epochs at 32 slots/epoch on Ethereum Mainnet.
-const FINALIZATION_TIMEOUT: u64 = 64;
+        tokio::time::sleep(RECONNECT_POLICY.backoff(attempt)).await;
+        counter!("hera_l1_reconnects_total").increment(1);
+        attempt = attempt.saturating_add(1);
+    }
+}
 
 /// A standalone context that polls for new blocks from an L1 node, depending
 /// on the URL scheme. Supported schemes are `http`, `ws`, and `file`.
@@ -33,17 +276,41 @@ const FINALIZATION_TIMEOUT: u64 = 64;
 /// `eth_getFilterChanges` if available, falling back to `eth_getBlockByNumber` if not.
 #[derive(Debug)]
 pub struct StandaloneHeraContext {
-    /// The current tip of the L1 chain listener, used to detect reorgs
+    /// The current tip of the L1 chain listener
     l1_tip: BlockNumber,
+    /// Hashes of the blocks in the current canonical L1 chain, used to detect reorgs.
+    /// Pruned together with the reorg cache.
+    canonical_chain: BTreeMap<BlockNumber, B256>,
     /// Channel that receives new blocks from the L1 node
     new_block_rx: mpsc::Receiver<Block<TxEnvelope>>,
     /// The highest block that was successfully processed by the driver.
     /// We can safely prune all cached blocks below this tip once they
     /// become finalized on L1.
     processed_tip: BlockNumber,
-    /// Cache of blocks that might be reorged out. In normal conditions,
-    /// this cache will not grow beyond [`FINALIZATION_TIMEOUT`] keys.
+    /// The last finalized L1 block number that was notified to the driver.
+    l1_finalized: BlockNumber,
+    /// Cache of blocks that might be reorged out. Blocks are only pruned once they
+    /// are both finalized on L1 and processed by the driver.
     reorg_cache: BTreeMap<BlockNumber, HashMap<B256, Block<TxEnvelope>>>,
+    /// Client used to backfill the blocks missed by the new block listener, if any
+    fetcher: Option<Box<dyn L1BlockFetcher>>,
+    /// Notifications that are ready to be sent to the driver, in order
+    pending: VecDeque<ChainNotification>,
+    /// The last received block followed by its backfilled ancestors, in descending order,
+    /// until they are linked to the tracked chain.
+    backfill_queue: Vec<Block<TxEnvelope>>,
+    /// The number of failed attempts to backfill the queued blocks.
+    backfill_attempts: u32,
+    /// Whether the context is currently connected to the L1 node
+    connected: Arc<AtomicBool>,
+    /// Receiver for the finalized L1 block number, if finality is pushed by a beacon node
+    /// instead of being polled from the L1 node.
+    finalized_rx: Option<watch::Receiver<BlockNumber>>,
+    /// The last time the finalized L1 block was polled from the L1 node, if ever.
+    last_finalized_poll: Option<Instant>,
+    /// The L1 cache of the chain provider, if any. Blocks that are reorged out
+    /// are removed from it so that they are fetched again from the L1 node.
+    l1_cache: Option<InMemoryChainProvider>,
     /// Handle to the background task that fetches and processes new blocks.
     _handle: JoinHandle<()>,
 }
@@ -56,10 +323,13 @@ impl StandaloneHeraContext {
             Self::with_http_poller(l1_rpc_url).await
         } else if l1_rpc_url.scheme().contains("ws") {
             debug!("Subscribing to new blocks via websocket");
-            Self::with_ws_subscriber(l1_rpc_url).await
+            Self::with_pubsub_subscriber(PubSubEndpoint::Ws(l1_rpc_url)).await
         } else if l1_rpc_url.scheme().contains("file") {
             debug!("Subscribing to new blocks via IPC");
-            Self::with_ipc_subscriber(l1_rpc_url).await
+            let path = l1_rpc_url
+                .to_file_path()
+                .map_err(|_| TransportErrorKind::custom_str("Invalid IPC file path"))?;
+            Self::with_pubsub_subscriber(PubSubEndpoint::Ipc(path)).await
         } else {
             Err(TransportErrorKind::custom_str("Unsupported URL scheme"))
         }
@@ -68,6 +338,7 @@ impl StandaloneHeraContext {
     /// Create a new standalone context that polls for new blocks via HTTP.
     async fn with_http_poller(l1_rpc_url: Url) -> TransportResult<Self> {
         let client = ReqwestProvider::<Ethereum>::new_http(l1_rpc_url);
+        let fetcher = client.root().clone().boxed();
         let (new_block_tx, new_block_rx) = mpsc::channel(128);
 
         let _handle = match client.watch_blocks().await {
@@ -78,8 +349,9 @@ impl StandaloneHeraContext {
                         match client.get_block_by_hash(hash, true.into()).await {
                             Ok(Some(block)) => {
                                 let block_with_txs = parse_reth_rpc_block(block);
-                                if let Err(e) = new_block_tx.try_send(block_with_txs) {
-                                    error!("Failed to send new block to channel: {:?}", e);
+                                if new_block_tx.send(block_with_txs).await.is_err() {
+                                    error!("New block channel closed, stopping the L1 listener");
+                                    return;
                                 }
                             }
                             Ok(None) => {
@@ -122,8 +394,9 @@ impl StandaloneHeraContext {
                                 {
                                     Ok(Some(full_block)) => {
                                         let block_with_txs = parse_reth_rpc_block(full_block);
-                                        if let Err(e) = new_block_tx.try_send(block_with_txs) {
-                                            error!("Failed to send new block to channel: {:?}", e);
+                                        if new_block_tx.send(block_with_txs).await.is_err() {
+                                            error!("New block channel closed, stopping the L1 listener");
+                                            return;
                                         }
                                     }
                                     other => {
@@ -144,53 +417,341 @@ impl StandaloneHeraContext {
             }
         };
 
-        Ok(Self::with_defaults(new_block_rx, _handle))
+        let ctx = Self::with_defaults(new_block_rx, Some(Box::new(fetcher)), _handle);
+        set_connected(&ctx.connected, true);
+        Ok(ctx)
     }
 
-    /// Create a new standalone context that subscribes to new blocks via websocket.
-    async fn with_ws_subscriber(l1_rpc_url: Url) -> TransportResult<Self> {
-        let ws = WsConnect::new(l1_rpc_url);
-        let client = ProviderBuilder::new().on_ws(ws).await?;
+    /// Create a new standalone context that subscribes to new blocks via websocket or IPC.
+    ///
+    /// The subscription is supervised: whenever it is closed, the context reconnects
+    /// with backoff and resubscribes. Blocks missed while disconnected are backfilled
+    /// when the next block is received.
+    async fn with_pubsub_subscriber(endpoint: PubSubEndpoint) -> TransportResult<Self> {
+        // Fail early if the L1 node can't be reached on startup
+        let (client, mut block_sub) = endpoint.subscribe().await?;
+        let fetcher = ReconnectingFetcher::new(client.boxed());
+        let connected = Arc::new(AtomicBool::new(false));
+        set_connected(&connected, true);
         let (new_block_tx, new_block_rx) = mpsc::channel(128);
 
-        let mut block_sub = client.subscribe_blocks().await?.into_stream();
-        let _handle = tokio::spawn(async move {
-            while let Some(block) = block_sub.next().await {
-                let block_with_txs_decoded = parse_reth_rpc_block(block);
-                if let Err(e) = new_block_tx.try_send(block_with_txs_decoded) {
-                    error!("Failed to send new block to channel: {:?}", e);
+        let _handle = tokio::spawn({
+            let fetcher = fetcher.clone();
+            let connected = connected.clone();
+            async move {
+                loop {
+                    while let Some(block) = block_sub.next().await {
+                        let block_with_txs_decoded = parse_reth_rpc_block(block);
+                        if new_block_tx.send(block_with_txs_decoded).await.is_err() {
+                            error!("New block channel closed, stopping the L1 listener");
+                            return;
+                        }
+                    }
+
+                    warn!("L1 block subscription closed, reconnecting");
+                    set_connected(&connected, false);
+                    block_sub = endpoint.resubscribe(&fetcher).await;
+                    set_connected(&connected, true);
                 }
             }
         });
 
-        Ok(Self::with_defaults(new_block_rx, _handle))
+        let mut ctx = Self::with_defaults(new_block_rx, Some(Box::new(fetcher)), _handle);
+        ctx.connected = connected;
+        Ok(ctx)
+    }
+
+    /// Create a new standalone context that follows the L1 head and finality from the
+    /// event stream of a beacon node, and fetches the execution blocks from the L1 node.
+    ///
+    /// The L1 RPC URL must be an HTTP URL. The event stream is reconnected with backoff
+    /// whenever it is closed.
+    pub fn with_beacon_events(l1_rpc_url: Url, l1_beacon_url: &Url) -> Self {
+        let fetcher = ReqwestProvider::<Ethereum>::new_http(l1_rpc_url).boxed();
+        Self::with_beacon_client(BeaconEventClient::new(l1_beacon_url), fetcher)
     }
 
-    /// Create a new standalone context that subscribes to new blocks via IPC.
-    async fn with_ipc_subscriber(l1_rpc_url: Url) -> TransportResult<Self> {
-        let ipc = IpcConnect::new(l1_rpc_url.to_file_path().expect("must be a file path"));
-        let client = ProviderBuilder::new().on_ipc(ipc).await?;
+    /// Create a new standalone context that follows the event stream of the given
+    /// beacon client, fetching the execution blocks with the given fetcher.
+    fn with_beacon_client<F>(beacon: BeaconEventClient, fetcher: F) -> Self
+    where
+        F: L1BlockFetcher + Clone + 'static,
+    {
         let (new_block_tx, new_block_rx) = mpsc::channel(128);
+        let (finalized_tx, finalized_rx) = watch::channel(0);
+        let connected = Arc::new(AtomicBool::new(false));
 
-        let mut block_sub = client.subscribe_blocks().await?.into_stream();
-        let _handle = tokio::spawn(async move {
-            while let Some(block) = block_sub.next().await {
-                let block_with_txs_decoded = parse_reth_rpc_block(block);
-                if let Err(e) = new_block_tx.try_send(block_with_txs_decoded) {
-                    error!("Failed to send new block to channel: {:?}", e);
-                }
-            }
-        });
+        let _handle = tokio::spawn(follow_beacon_events(
+            beacon,
+            fetcher.clone(),
+            new_block_tx,
+            finalized_tx,
+            connected.clone(),
+        ));
 
-        Ok(Self::with_defaults(new_block_rx, _handle))
+        let mut ctx = Self::with_defaults(new_block_rx, Some(Box::new(fetcher)), _handle);
+        ctx.connected = connected;
+        ctx.finalized_rx = Some(finalized_rx);
+        ctx
     }
 
     /// Create a new standalone context with the given new block receiver and handle.
     fn with_defaults(
         new_block_rx: mpsc::Receiver<Block<TxEnvelope>>,
+        fetcher: Option<Box<dyn L1BlockFetcher>>,
         _handle: JoinHandle<()>,
     ) -> Self {
-        Self { new_block_rx, _handle, l1_tip: 0, processed_tip: 0, reorg_cache: BTreeMap::new() }
+        Self {
+            new_block_rx,
+            fetcher,
+            pending: VecDeque::new(),
+            backfill_queue: Vec::new(),
+            backfill_attempts: 0,
+            connected: Arc::new(AtomicBool::new(true)),
+            finalized_rx: None,
+            last_finalized_poll: None,
+            l1_cache: None,
+            _handle,
+            l1_tip: 0,
+            processed_tip: 0,
+            l1_finalized: 0,
+            canonical_chain: BTreeMap::new(),
+            reorg_cache: BTreeMap::new(),
+        }
+    }
+
+    /// Set the L1 cache of the chain provider, to be invalidated on L1 reorgs.
+    pub fn with_l1_cache(mut self, l1_cache: InMemoryChainProvider) -> Self {
+        self.l1_cache = Some(l1_cache);
+        self
+    }
+
+    /// Returns a handle to the connection state of the context to the L1 node.
+    ///
+    /// This is always true when polling via HTTP, as no connection is maintained.
+    pub fn l1_connection(&self) -> Arc<AtomicBool> {
+        self.connected.clone()
+    }
+
+    /// Returns the finalized L1 block number from the L1 node, if it moved
+    /// forward since the last time it was checked.
+    ///
+    /// The L1 node is polled at most once per [FINALIZED_POLL_INTERVAL].
+    async fn poll_finalized_block(&mut self) -> Option<BlockNumber> {
+        let finalized = match &self.finalized_rx {
+            Some(finalized_rx) => Ok(Some(*finalized_rx.borrow())),
+            None => {
+                let fetcher = self.fetcher.as_ref()?;
+                if self.last_finalized_poll.is_some_and(|at| at.elapsed() < FINALIZED_POLL_INTERVAL)
+                {
+                    return None;
+                }
+                self.last_finalized_poll = Some(Instant::now());
+                fetcher.finalized_block_number().await
+            }
+        };
+
+        match finalized {
+            Ok(Some(finalized)) if finalized > self.l1_finalized => {
+                self.l1_finalized = finalized;
+                self.prune();
+                Some(finalized)
+            }
+            Ok(_) => None,
+            Err(err) => {
+                warn!("Failed to fetch the finalized L1 block: {:?}", err);
+                None
+            }
+        }
+    }
+
+    /// Prune the reorg cache below the finalized L1 block, as finalized blocks are no
+    /// longer candidates for reorgs. Blocks that were not processed by the driver yet
+    /// are kept, so that they can still be reverted.
+    fn prune(&mut self) {
+        let prune_below = self.l1_finalized.min(self.processed_tip);
+        self.reorg_cache.retain(|num, _| *num >= prune_below);
+        self.canonical_chain.retain(|num, _| *num >= prune_below);
+    }
+
+    /// Fetch the ancestors of the queued blocks that were never received, so that the
+    /// chain can be extended without gaps.
+    ///
+    /// Each fetched ancestor is queued as soon as it is received, so that an interrupted
+    /// backfill resumes where it stopped. On error, the queued blocks are kept so that
+    /// the backfill can be retried.
+    async fn backfill(&mut self) -> Result<(), BackfillError> {
+        let Some(fetcher) = &self.fetcher else {
+            return Ok(());
+        };
+
+        while let Some(cursor) = self.backfill_queue.last().map(|b| b.header.clone()) {
+            let (parent_num, parent_hash) = (cursor.number.saturating_sub(1), cursor.parent_hash);
+            if !self.is_missing(parent_num, parent_hash) {
+                break;
+            }
+            if self.backfill_queue.len() as u64 > MAX_BACKFILL_DEPTH {
+                return Err(BackfillError::TooDeep);
+            }
+
+            match fetcher.block_by_hash(parent_hash).await {
+                Ok(Some(parent)) => self.backfill_queue.push(parent),
+                Ok(None) => return Err(BackfillError::NotFound(parent_hash)),
+                Err(e) => return Err(BackfillError::Transport(parent_hash, e)),
+            }
+        }
+
+        if self.backfill_queue.len() > 1 {
+            debug!("Backfilled {} missing L1 blocks", self.backfill_queue.len() - 1);
+        }
+        Ok(())
+    }
+
+    /// Handle a failed backfill: re-anchor the tracked chain if too many blocks are missing,
+    /// otherwise wait before the next attempt, or drop the queued blocks once all attempts
+    /// failed. Nothing is sent to the driver for the dropped blocks, as they can't be linked
+    /// to the tracked chain.
+    async fn handle_backfill_error(&mut self, err: BackfillError) {
+        counter!("hera_l1_backfill_failures_total").increment(1);
+        let tip = self.backfill_queue.first().map(|b| b.header.number).unwrap_or_default();
+        self.backfill_attempts += 1;
+
+        if matches!(err, BackfillError::TooDeep) {
+            warn!(
+                "More than {} L1 blocks are missing below {}, re-anchoring on finalized block {}",
+                MAX_BACKFILL_DEPTH, tip, self.l1_finalized
+            );
+            let notification = self.reanchor();
+            self.pending.push_back(notification);
+        } else if self.backfill_attempts < BACKFILL_RETRY_POLICY.max_attempts() {
+            let backoff = BACKFILL_RETRY_POLICY.backoff(self.backfill_attempts - 1);
+            warn!("Failed to backfill L1 blocks below {}, retrying: {}", tip, err);
+            tokio::time::sleep(backoff).await;
+            return;
+        } else {
+            error!("Failed to backfill L1 blocks below {}, dropping them: {}", tip, err);
+        }
+
+        self.backfill_queue.clear();
+        self.backfill_attempts = 0;
+    }
+
+    /// Replace the tracked chain with the queued blocks, which can't be linked to it.
+    ///
+    /// A reorg may have happened within the gap, so the tracked blocks above the finalized
+    /// L1 block are reverted: the driver resets derivation to the finalized block, and the
+    /// blocks in the gap are fetched again by number by the derivation pipeline.
+    fn reanchor(&mut self) -> ChainNotification {
+        let old_blocks = self
+            .canonical_chain
+            .range(self.l1_finalized + 1..)
+            .filter_map(|(num, hash)| self.reorg_cache.get(num)?.get(hash).cloned())
+            .collect::<Vec<_>>();
+
+        self.canonical_chain.clear();
+        self.reorg_cache.clear();
+        let new_blocks = std::mem::take(&mut self.backfill_queue);
+        for block in &new_blocks {
+            self.canonical_chain.insert(block.header.number, block.header.hash);
+            self.reorg_cache
+                .entry(block.header.number)
+                .or_default()
+                .insert(block.header.hash, block.clone());
+        }
+        self.l1_tip = new_blocks[0].header.number;
+        if let Some(l1_cache) = &mut self.l1_cache {
+            l1_cache.revert_to(self.l1_finalized);
+        }
+
+        let new_blocks = Blocks::from(new_blocks);
+        if old_blocks.is_empty() {
+            return ChainNotification::New { new_blocks };
+        }
+        ChainNotification::Reorg { old_blocks: Blocks::from(old_blocks), new_blocks }
+    }
+
+    /// Returns true if the given block is within the tracked chain range but was
+    /// never received.
+    fn is_missing(&self, number: BlockNumber, hash: B256) -> bool {
+        let Some((oldest, _)) = self.canonical_chain.first_key_value() else {
+            // Nothing to backfill before the first received block
+            return false;
+        };
+
+        number >= *oldest && !self.reorg_cache.get(&number).is_some_and(|b| b.contains_key(&hash))
+    }
+
+    /// Process a new block received from the L1 node and make it the new canonical tip.
+    ///
+    /// If the block does not extend the current canonical chain, walk back through the
+    /// reorg cache until the common ancestor and return a reorg notification with the
+    /// reorged out and new canonical blocks. Returns `None` if the block was already
+    /// processed.
+    fn handle_new_block(&mut self, block: Block<TxEnvelope>) -> Option<ChainNotification> {
+        let (block_num, block_hash) = (block.header.number, block.header.hash);
+        if self.canonical_chain.get(&block_num) == Some(&block_hash) {
+            debug!("Ignoring already processed L1 block {}", block_num);
+            return None;
+        }
+
+        let entry = self.reorg_cache.entry(block_num).or_default();
+        entry.insert(block_hash, block.clone());
+
+        // Walk back from the new block until we find its ancestor on the canonical chain.
+        // Blocks with no canonical counterpart are either new or older than the cache.
+        let mut new_blocks = vec![block];
+        let common_ancestor = loop {
+            let cursor = &new_blocks[new_blocks.len() - 1].header;
+            let (parent_num, parent_hash) = (cursor.number.saturating_sub(1), cursor.parent_hash);
+            match self.canonical_chain.get(&parent_num) {
+                None => break parent_num,
+                Some(canonical_hash) if *canonical_hash == parent_hash => break parent_num,
+                Some(_) => {
+                    match self.reorg_cache.get(&parent_num).and_then(|b| b.get(&parent_hash)) {
+                        Some(parent) => new_blocks.push(parent.clone()),
+                        None => {
+                            warn!(
+                            "L1 block {} ({}) not found in the reorg cache, reorg may be incomplete",
+                            parent_num, parent_hash
+                        );
+                            break parent_num.saturating_sub(1);
+                        }
+                    }
+                }
+            }
+        };
+
+        // Collect the canonical blocks that are no longer part of the chain
+        let old_blocks = self
+            .canonical_chain
+            .split_off(&(common_ancestor + 1))
+            .into_iter()
+            .filter_map(|(num, hash)| self.reorg_cache.get(&num)?.get(&hash).cloned())
+            .collect::<Vec<_>>();
+
+        for new_block in &new_blocks {
+            self.canonical_chain.insert(new_block.header.number, new_block.header.hash);
+        }
+
+        let new_blocks = Blocks::from(new_blocks);
+        if old_blocks.is_empty() {
+            self.l1_tip = block_num;
+            return Some(ChainNotification::New { new_blocks });
+        }
+
+        info!(
+            "L1 reorg detected: tip {} -> {}, common ancestor {} ({} blocks reorged out)",
+            self.l1_tip,
+            block_num,
+            common_ancestor,
+            old_blocks.len()
+        );
+        self.l1_tip = block_num;
+        if let Some(l1_cache) = &mut self.l1_cache {
+            l1_cache.revert_to(common_ancestor);
+        }
+
+        Some(ChainNotification::Reorg { old_blocks: Blocks::from(old_blocks), new_blocks })
     }
 }
 
@@ -201,27 +762,39 @@ impl DriverContext for StandaloneHeraContext {
         // have a fallback online RPC for that downstream? The driver and provider should be
         // generic but currently are very coupled to the node mode (standalone vs exex).
 
-        let block = self.new_block_rx.recv().await?;
-        let block_num = block.header.number;
+        // This future is cancelled by the driver whenever an unsafe L2 block is received,
+        // so all the progress is kept in the context state before awaiting.
+        loop {
+            if let Some(notification) = self.pending.pop_front() {
+                return Some(notification);
+            }
 
-        let entry = self.reorg_cache.entry(block_num).or_default();
-        entry.insert(block.header.hash, block.clone());
+            if let Some(l1_block) = self.poll_finalized_block().await {
+                return Some(ChainNotification::Finalized { l1_block });
+            }
 
-        if block_num <= self.l1_tip {
-            todo!("handle reorgs");
-        } else {
-            self.l1_tip = block_num;
+            if self.backfill_queue.is_empty() {
+                let block = self.new_block_rx.recv().await?;
+                self.backfill_queue.push(block);
+            }
 
-            // upon a new tip, prune the reorg cache for all blocks that have been finalized,
-            // as they are no longer candidates for reorgs.
-            self.reorg_cache.retain(|num, _| *num > block_num - FINALIZATION_TIMEOUT);
-        }
+            if let Err(err) = self.backfill().await {
+                self.handle_backfill_error(err).await;
+                continue;
+            }
 
-        Some(ChainNotification::New { new_blocks: Blocks::from(block) })
+            self.backfill_attempts = 0;
+            while let Some(block) = self.backfill_queue.pop() {
+                if let Some(notification) = self.handle_new_block(block) {
+                    self.pending.push_back(notification);
+                }
+            }
+        }
     }
 
     fn send_processed_tip_event(&mut self, tip: BlockNumber) -> Result<(), SendError<BlockNumber>> {
         self.processed_tip = tip;
+        self.prune();
         Ok(())
     }
 }
@@ -249,7 +822,8 @@ fn parse_reth_rpc_block(block: Block) -> Block<TxEnvelope> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy::rpc::types::Header;
+    use alloy::{eips::BlockNumHash, rpc::types::Header};
+    use std::sync::atomic::AtomicU64;
 
     #[tokio::test]
     async fn test_http_poller() -> eyre::Result<()> {
@@ -289,43 +863,414 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_pubsub_unreachable_on_startup() {
+        let url = Url::parse("ws://127.0.0.1:1").unwrap();
+        assert!(StandaloneHeraContext::new(url).await.is_err());
+    }
+
+    /// Serve a beacon node stub that streams the given events once per connection and
+    /// maps the beacon block roots to the given execution blocks.
+    async fn serve_beacon_stub(events: String, blocks: HashMap<B256, BlockNumHash>) -> Url {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![0; 4096];
+                let len = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..len]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+
+                let response = if path.starts_with("/eth/v1/events") {
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\
+                         connection: close\r\n\r\n{events}"
+                    )
+                } else {
+                    let root = path.rsplit('/').next().unwrap().parse::<B256>().unwrap();
+                    let block = blocks[&root];
+                    let body = format!(
+                        r#"{{"data":{{"message":{{"body":{{"execution_payload_header":{{"block_number":"{}","block_hash":"{}"}}}}}}}}}}"#,
+                        block.number, block.hash
+                    );
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                         content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                };
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_beacon_events() {
+        let genesis = create_mock_block(0);
+        let chain = create_mock_chain(&genesis, 4);
+        let roots = (1..=4).map(B256::with_last_byte).collect::<Vec<_>>();
+        let blocks = roots
+            .iter()
+            .zip(&chain)
+            .map(|(root, b)| (*root, BlockNumHash::new(b.header.number, b.header.hash)))
+            .collect();
+
+        // Block 3 is only announced through the reorg event, block 2 is never announced
+        let events = format!(
+            "event: head\ndata: {{\"block\":\"{}\"}}\n\n\
+             event: finalized_checkpoint\ndata: {{\"block\":\"{}\"}}\n\n\
+             event: chain_reorg\ndata: {{\"depth\":\"1\",\"new_head_block\":\"{}\"}}\n\n\
+             event: head\ndata: {{\"block\":\"{}\"}}\n\n",
+            roots[0], roots[0], roots[2], roots[3]
+        );
+        let beacon_url = serve_beacon_stub(events, blocks).await;
+
+        let fetcher = MockFetcher {
+            blocks: chain.iter().map(|b| (b.header.hash, b.clone())).collect(),
+            ..Default::default()
+        };
+        let mut ctx =
+            StandaloneHeraContext::with_beacon_client(BeaconEventClient::new(&beacon_url), fetcher);
+
+        let mut new_blocks = Vec::new();
+        let mut finalized = None;
+        while new_blocks.len() < 4 || finalized.is_none() {
+            let notif = ctx.recv_notification().await.unwrap();
+            assert!(notif.reverted_chain().is_none());
+            if let Some(blocks) = notif.new_chain() {
+                new_blocks.extend(blocks.0.values().map(|b| b.header.hash));
+            }
+            finalized = finalized.or(notif.finalized_block());
+        }
+
+        assert_eq!(new_blocks, chain.iter().map(|b| b.header.hash).collect::<Vec<_>>());
+        assert_eq!(finalized, Some(1));
+    }
+
     #[tokio::test]
     async fn test_reorg_cache_pruning() {
-        let (_, rx) = mpsc::channel(128);
-        let handle = tokio::spawn(async {});
-        let mut ctx = StandaloneHeraContext::with_defaults(rx, handle);
+        let genesis = create_mock_block(0);
+        let chain = create_mock_chain(&genesis, 10);
+        let (tx, mut ctx) = ctx_with_fetcher(&[], Some(5));
 
-        // Simulate receiving 100 blocks
-        for i in 1..=100 {
-            let block = create_mock_block(i);
-            ctx.reorg_cache.entry(i).or_default().insert(block.header.hash, block);
+        for block in &chain {
+            tx.send(block.clone()).await.unwrap();
         }
 
-        // Simulate receiving a new block that should trigger pruning
-        let new_block = create_mock_block(101);
-        ctx.l1_tip = 101;
-        ctx.reorg_cache.entry(101).or_default().insert(new_block.header.hash, new_block.clone());
+        // The finality update is sent before any new block
+        let notif = ctx.recv_notification().await.unwrap();
+        assert_eq!(notif.finalized_block(), Some(5));
+        for block in &chain {
+            let notif = ctx.recv_notification().await.unwrap();
+            assert_eq!(notif.new_chain().unwrap().tip(), block.header.number);
+        }
+        assert_eq!(ctx.reorg_cache.len(), 10);
 
-        // Manually call the pruning logic
-        ctx.reorg_cache.retain(|num, _| *num > 101 - FINALIZATION_TIMEOUT);
+        // Blocks are only pruned below the processed tip
+        ctx.send_processed_tip_event(3).unwrap();
+        assert_eq!(
+            ctx.reorg_cache.keys().copied().collect::<Vec<_>>(),
+            (3..=10).collect::<Vec<_>>()
+        );
 
-        // Check that only the last FINALIZATION_BLOCKS are kept
-        assert_eq!(ctx.reorg_cache.len(), FINALIZATION_TIMEOUT as usize);
-        assert!(ctx.reorg_cache.contains_key(&(101 - FINALIZATION_TIMEOUT + 1)));
-        assert!(!ctx.reorg_cache.contains_key(&(101 - FINALIZATION_TIMEOUT)));
+        // And below the finalized block
+        ctx.send_processed_tip_event(10).unwrap();
+        assert_eq!(
+            ctx.reorg_cache.keys().copied().collect::<Vec<_>>(),
+            (5..=10).collect::<Vec<_>>()
+        );
+        assert_eq!(ctx.canonical_chain.len(), 6);
     }
 
     #[tokio::test]
     async fn test_send_processed_tip_event() {
         let (_, rx) = mpsc::channel(128);
         let handle = tokio::spawn(async {});
-        let mut ctx = StandaloneHeraContext::with_defaults(rx, handle);
+        let mut ctx = StandaloneHeraContext::with_defaults(rx, None, handle);
 
         // Send a processed tip event
         let result = ctx.send_processed_tip_event(100);
         assert!(result.is_ok());
     }
 
+    async fn assert_reorg_at_depth(depth: u64) {
+        let (tx, rx) = mpsc::channel(128);
+        let handle = tokio::spawn(async {});
+        let mut ctx = StandaloneHeraContext::with_defaults(rx, None, handle);
+
+        let genesis = create_mock_block(0);
+        let chain_a = create_mock_chain(&genesis, 20);
+        for block in &chain_a {
+            tx.send(block.clone()).await.unwrap();
+            let notif = ctx.recv_notification().await.unwrap();
+            assert!(notif.reverted_chain().is_none());
+            assert_eq!(notif.new_chain().unwrap().tip(), block.header.number);
+        }
+
+        // Reorg out the last `depth` blocks of chain A
+        let fork_point = &chain_a[(19 - depth) as usize];
+        let chain_b = create_mock_chain(fork_point, depth);
+        tx.send(chain_b[0].clone()).await.unwrap();
+        let notif = ctx.recv_notification().await.unwrap();
+        assert_blocks(&notif.reverted_chain().unwrap(), &chain_a[(20 - depth) as usize..]);
+        assert_blocks(&notif.new_chain().unwrap(), &chain_b[..1]);
+
+        for block in &chain_b[1..] {
+            tx.send(block.clone()).await.unwrap();
+            let notif = ctx.recv_notification().await.unwrap();
+            assert!(notif.reverted_chain().is_none());
+            assert_blocks(&notif.new_chain().unwrap(), std::slice::from_ref(block));
+        }
+        assert_eq!(ctx.l1_tip, 20);
+
+        // Extend chain A again: walk back through the cached chain A blocks
+        let next_a = create_mock_chain(chain_a.last().unwrap(), 1);
+        tx.send(next_a[0].clone()).await.unwrap();
+        let notif = ctx.recv_notification().await.unwrap();
+        assert_eq!(notif.reverted_chain().unwrap().fork_block(), fork_point.header.number);
+        assert_blocks(&notif.reverted_chain().unwrap(), &chain_b);
+        let mut expected = chain_a[(20 - depth) as usize..].to_vec();
+        expected.push(next_a[0].clone());
+        assert_blocks(&notif.new_chain().unwrap(), &expected);
+        assert_eq!(ctx.l1_tip, 21);
+
+        // Receiving the same tip again is a no-op
+        tx.send(next_a[0].clone()).await.unwrap();
+        drop(tx);
+        assert!(ctx.recv_notification().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reorg_depth_1() {
+        assert_reorg_at_depth(1).await;
+    }
+
+    #[tokio::test]
+    async fn test_reorg_depth_2() {
+        assert_reorg_at_depth(2).await;
+    }
+
+    #[tokio::test]
+    async fn test_reorg_depth_10() {
+        assert_reorg_at_depth(10).await;
+    }
+
+    #[tokio::test]
+    async fn test_reorg_missing_ancestor() {
+        let (_, rx) = mpsc::channel(128);
+        let handle = tokio::spawn(async {});
+        let mut ctx = StandaloneHeraContext::with_defaults(rx, None, handle);
+
+        let genesis = create_mock_block(0);
+        let chain_a = create_mock_chain(&genesis, 10);
+        for block in &chain_a {
+            ctx.handle_new_block(block.clone()).unwrap();
+        }
+
+        // The parent of the new tip was never received
+        let chain_b = create_mock_chain(&chain_a[7], 3);
+        let notif = ctx.handle_new_block(chain_b[2].clone()).unwrap();
+        assert_blocks(&notif.reverted_chain().unwrap(), &chain_a[9..]);
+        assert_blocks(&notif.new_chain().unwrap(), &chain_b[2..]);
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct MockFetcher {
+        blocks: HashMap<B256, Block<TxEnvelope>>,
+        finalized: Option<BlockNumber>,
+        delay: Option<Duration>,
+        finalized_polls: Arc<AtomicU64>,
+    }
+
+    #[async_trait]
+    impl L1BlockFetcher for MockFetcher {
+        async fn block_by_hash(&self, hash: B256) -> TransportResult<Option<Block<TxEnvelope>>> {
+            if let Some(delay) = self.delay {
+                tokio::time::sleep(delay).await;
+            }
+            Ok(self.blocks.get(&hash).cloned())
+        }
+
+        async fn finalized_block_number(&self) -> TransportResult<Option<BlockNumber>> {
+            self.finalized_polls.fetch_add(1, Ordering::Relaxed);
+            Ok(self.finalized)
+        }
+    }
+
+    fn ctx_with_fetcher(
+        blocks: &[Block<TxEnvelope>],
+        finalized: Option<BlockNumber>,
+    ) -> (mpsc::Sender<Block<TxEnvelope>>, StandaloneHeraContext) {
+        let (tx, rx) = mpsc::channel(128);
+        let handle = tokio::spawn(async {});
+        let blocks = blocks.iter().map(|b| (b.header.hash, b.clone())).collect();
+        let fetcher = MockFetcher { blocks, finalized, ..Default::default() };
+        (tx, StandaloneHeraContext::with_defaults(rx, Some(Box::new(fetcher)), handle))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_finalized_block_polling_is_rate_limited() {
+        let genesis = create_mock_block(0);
+        let chain = create_mock_chain(&genesis, 4);
+        let (tx, rx) = mpsc::channel(128);
+        let fetcher = MockFetcher { finalized: Some(1), ..Default::default() };
+        let polls = fetcher.finalized_polls.clone();
+        let handle = tokio::spawn(async {});
+        let mut ctx = StandaloneHeraContext::with_defaults(rx, Some(Box::new(fetcher)), handle);
+
+        for block in &chain[..3] {
+            tx.send(block.clone()).await.unwrap();
+        }
+        assert_eq!(ctx.recv_notification().await.unwrap().finalized_block(), Some(1));
+        for _ in &chain[..3] {
+            ctx.recv_notification().await.unwrap();
+        }
+        assert_eq!(polls.load(Ordering::Relaxed), 1);
+
+        tokio::time::advance(FINALIZED_POLL_INTERVAL).await;
+        tx.send(chain[3].clone()).await.unwrap();
+        ctx.recv_notification().await.unwrap();
+        assert_eq!(polls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_incomplete_backfill_is_not_sent() {
+        let genesis = create_mock_block(0);
+        let chain = create_mock_chain(&genesis, 10);
+
+        // Block 7 can't be fetched, so block 10 can't be linked to the chain
+        let known = chain.iter().filter(|b| b.header.number != 7).cloned().collect::<Vec<_>>();
+        let (tx, mut ctx) = ctx_with_fetcher(&known, None);
+        for block in chain[..5].iter().chain(&chain[9..]) {
+            tx.send(block.clone()).await.unwrap();
+        }
+        drop(tx);
+
+        for block in &chain[..5] {
+            let notif = ctx.recv_notification().await.unwrap();
+            assert_blocks(&notif.new_chain().unwrap(), std::slice::from_ref(block));
+        }
+        assert!(ctx.recv_notification().await.is_none());
+        assert!(ctx.backfill_queue.is_empty());
+        assert_eq!(ctx.l1_tip, 5);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_recv_notification_is_cancel_safe() {
+        let genesis = create_mock_block(0);
+        let chain = create_mock_chain(&genesis, 4);
+        let (tx, rx) = mpsc::channel(128);
+        let fetcher = MockFetcher {
+            blocks: chain.iter().map(|b| (b.header.hash, b.clone())).collect(),
+            delay: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let handle = tokio::spawn(async {});
+        let mut ctx = StandaloneHeraContext::with_defaults(rx, Some(Box::new(fetcher)), handle);
+
+        tx.send(chain[0].clone()).await.unwrap();
+        ctx.recv_notification().await.unwrap();
+
+        // Cancel the notification while blocks 2 and 3 are being backfilled below block 4
+        tx.send(chain[3].clone()).await.unwrap();
+        let cancelled =
+            tokio::time::timeout(Duration::from_millis(1500), ctx.recv_notification()).await;
+        assert!(cancelled.is_err());
+        assert_eq!(ctx.backfill_queue.len(), 2);
+
+        for block in &chain[1..] {
+            let notif = ctx.recv_notification().await.unwrap();
+            assert_blocks(&notif.new_chain().unwrap(), std::slice::from_ref(block));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backfill_number_gap() {
+        let genesis = create_mock_block(0);
+        let chain = create_mock_chain(&genesis, 10);
+        let (tx, mut ctx) = ctx_with_fetcher(&chain, None);
+
+        for block in chain[..5].iter().chain(&chain[9..]) {
+            tx.send(block.clone()).await.unwrap();
+        }
+        drop(tx);
+
+        for block in &chain {
+            let notif = ctx.recv_notification().await.unwrap();
+            assert!(notif.reverted_chain().is_none());
+            assert_blocks(&notif.new_chain().unwrap(), std::slice::from_ref(block));
+        }
+        assert!(ctx.recv_notification().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_backfill_missed_fork_blocks() {
+        let genesis = create_mock_block(0);
+        let chain_a = create_mock_chain(&genesis, 10);
+        let chain_b = create_mock_chain(&chain_a[6], 5);
+        let (tx, mut ctx) = ctx_with_fetcher(&[chain_a.clone(), chain_b.clone()].concat(), None);
+
+        for block in &chain_a {
+            tx.send(block.clone()).await.unwrap();
+            ctx.recv_notification().await.unwrap();
+        }
+
+        // Only the tip of the fork is received
+        tx.send(chain_b[4].clone()).await.unwrap();
+        drop(tx);
+
+        let notif = ctx.recv_notification().await.unwrap();
+        assert_blocks(&notif.reverted_chain().unwrap(), &chain_a[7..]);
+        assert_blocks(&notif.new_chain().unwrap(), &chain_b[..1]);
+        for block in &chain_b[1..] {
+            let notif = ctx.recv_notification().await.unwrap();
+            assert!(notif.reverted_chain().is_none());
+            assert_blocks(&notif.new_chain().unwrap(), std::slice::from_ref(block));
+        }
+        assert!(ctx.recv_notification().await.is_none());
+        assert_eq!(ctx.l1_tip, 12);
+    }
+
+    #[tokio::test]
+    async fn test_backfill_too_deep_reanchors_on_finalized_block() {
+        let genesis = create_mock_block(0);
+        let chain = create_mock_chain(&genesis, 301);
+        let (tx, mut ctx) = ctx_with_fetcher(&chain, Some(3));
+
+        for block in &chain[..5] {
+            tx.send(block.clone()).await.unwrap();
+        }
+        assert_eq!(ctx.recv_notification().await.unwrap().finalized_block(), Some(3));
+        for _ in &chain[..5] {
+            ctx.recv_notification().await.unwrap();
+        }
+
+        // Blocks 6 to 43 can't be backfilled below block 300
+        tx.send(chain[299].clone()).await.unwrap();
+        let notif = ctx.recv_notification().await.unwrap();
+        assert_eq!(notif.reverted_chain().unwrap().fork_block(), 3);
+        assert_blocks(&notif.reverted_chain().unwrap(), &chain[3..5]);
+        assert_blocks(&notif.new_chain().unwrap(), &chain[43..300]);
+        assert_eq!(ctx.l1_tip, 300);
+
+        // The next block extends the re-anchored chain without backfilling again
+        tx.send(chain[300].clone()).await.unwrap();
+        drop(tx);
+        let notif = ctx.recv_notification().await.unwrap();
+        assert!(notif.reverted_chain().is_none());
+        assert_blocks(&notif.new_chain().unwrap(), &chain[300..]);
+        assert!(ctx.recv_notification().await.is_none());
+    }
+
+    fn assert_blocks(blocks: &Blocks, expected: &[Block<TxEnvelope>]) {
+        let hashes = blocks.0.values().map(|b| b.header.hash).collect::<Vec<_>>();
+        let expected = expected.iter().map(|b| b.header.hash).collect::<Vec<_>>();
+        assert_eq!(hashes, expected);
+    }
+
     // Helper function to create a mock Block<TxEnvelope>
     fn create_mock_block(number: u64) -> Block<TxEnvelope> {
         Block {
@@ -336,4 +1281,16 @@ mod tests {
             withdrawals: None,
         }
     }
+
+    // Helper function to create a chain of `len` mock blocks on top of `parent`
+    fn create_mock_chain(parent: &Block<TxEnvelope>, len: u64) -> Vec<Block<TxEnvelope>> {
+        let mut chain: Vec<Block<TxEnvelope>> = Vec::new();
+        for _ in 0..len {
+            let parent = chain.last().unwrap_or(parent);
+            let mut block = create_mock_block(parent.header.number + 1);
+            block.header.parent_hash = parent.header.hash;
+            chain.push(block);
+        }
+        chain
+    }
 }
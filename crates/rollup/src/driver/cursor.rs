@@ -3,6 +3,18 @@ use std::collections::{BTreeMap, VecDeque};
 
 use op_alloy_protocol::{BlockInfo, L2BlockInfo};
 
+/// The L2 chain heads tracked by the driver.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct L2Heads {
+    /// The unsafe L2 head: the latest L2 block known to the node,
+    /// which may not have been derived from L1 data yet.
+    pub unsafe_head: L2BlockInfo,
+    /// The safe L2 head: the latest L2 block derived from L1 data.
+    pub safe_head: L2BlockInfo,
+    /// The finalized L2 head: the latest L2 block whose L1 origin is finalized on L1.
+    pub finalized_head: L2BlockInfo,
+}
+
 /// A cursor that keeps track of the L2 tip block for a given L1 origin block.
 ///
 /// The cursor is used to advance the pipeline to the next L2 block, and to reset
@@ -21,6 +33,8 @@ pub struct SyncCursor {
     l1_origin_block_info: HashMap<u64, BlockInfo>,
     /// Map of the L1 origin block number to its corresponding tip L2 block
     l1_origin_to_l2_blocks: BTreeMap<u64, L2BlockInfo>,
+    /// The unsafe, safe and finalized L2 heads.
+    heads: L2Heads,
 }
 
 impl SyncCursor {
@@ -37,6 +51,7 @@ impl SyncCursor {
             l1_origin_key_order: VecDeque::with_capacity(capacity),
             l1_origin_block_info: HashMap::with_capacity(capacity),
             l1_origin_to_l2_blocks: BTreeMap::new(),
+            heads: L2Heads::default(),
         }
     }
 
@@ -49,9 +64,39 @@ impl SyncCursor {
         }
     }
 
+    /// Get the current unsafe, safe and finalized L2 heads.
+    pub const fn heads(&self) -> L2Heads {
+        self.heads
+    }
+
+    /// Get the L1 origin block info for the given L1 block number, if it is in the cache.
+    pub fn l1_origin(&self, number: u64) -> Option<BlockInfo> {
+        self.l1_origin_block_info.get(&number).copied()
+    }
+
+    /// Get the cursor entries: each L1 origin block with its corresponding tip L2 block,
+    /// ordered by L1 origin block number.
+    pub fn entries(&self) -> Vec<(BlockInfo, L2BlockInfo)> {
+        self.l1_origin_to_l2_blocks
+            .iter()
+            .filter_map(|(l1, l2)| self.l1_origin_block_info.get(l1).map(|info| (*info, *l2)))
+            .collect()
+    }
+
+    /// Restore the cursor from previously persisted entries and heads.
+    ///
+    /// The entries must be ordered by L1 origin block number. The safe head is set
+    /// to the last entry, while the finalized head is restored as-is.
+    pub fn restore(&mut self, entries: Vec<(BlockInfo, L2BlockInfo)>, finalized_head: L2BlockInfo) {
+        for (l1_origin_block, l2_tip_block) in entries {
+            self.advance(l1_origin_block, l2_tip_block);
+        }
+        self.heads.finalized_head = finalized_head;
+    }
+
     /// Advance the cursor to the provided L2 block, given the corresponding L1 origin block.
     ///
-    /// If the cache is full, the oldest entry is evicted.
+    /// The new L2 block becomes the safe head. If the cache is full, the oldest entry is evicted.
     pub fn advance(&mut self, l1_origin_block: BlockInfo, l2_tip_block: L2BlockInfo) {
         if self.l1_origin_to_l2_blocks.len() >= self.capacity {
             let key = self.l1_origin_key_order.pop_front().unwrap();
@@ -61,32 +106,165 @@ impl SyncCursor {
         self.l1_origin_key_order.push_back(l1_origin_block.number);
         self.l1_origin_block_info.insert(l1_origin_block.number, l1_origin_block);
         self.l1_origin_to_l2_blocks.insert(l1_origin_block.number, l2_tip_block);
+
+        self.heads.safe_head = l2_tip_block;
+        if self.heads.unsafe_head.block_info.number < l2_tip_block.block_info.number {
+            self.heads.unsafe_head = l2_tip_block;
+        }
+    }
+
+    /// Set the unsafe L2 head to the provided block, e.g. after an unsafe block was
+    /// received from the p2p network, or after the unsafe chain was reorged out.
+    pub fn update_unsafe_head(&mut self, unsafe_head: L2BlockInfo) {
+        self.heads.unsafe_head = unsafe_head;
+    }
+
+    /// Mark all L2 blocks with an L1 origin at or below the given finalized L1 block
+    /// number as finalized.
+    ///
+    /// Returns the new finalized L2 head if it moved forward.
+    pub fn finalize(&mut self, l1_finalized: u64) -> Option<L2BlockInfo> {
+        let (_, l2_block) = self.l1_origin_to_l2_blocks.range(..=l1_finalized).next_back()?;
+
+        // The finalized head can never be ahead of the safe head.
+        let l2_block = if l2_block.block_info.number > self.heads.safe_head.block_info.number {
+            self.heads.safe_head
+        } else {
+            *l2_block
+        };
+
+        if l2_block.block_info.number <= self.heads.finalized_head.block_info.number {
+            return None;
+        }
+
+        self.heads.finalized_head = l2_block;
+        Some(l2_block)
     }
 
     /// When the L1 undergoes a reorg, we need to reset the cursor to the fork block minus
     /// the channel timeout, because an L2 block might have started to be derived at the
     /// beginning of the channel.
     ///
+    /// All entries after the new cursor state are dropped, and the unsafe and safe heads
+    /// are moved back to the new cursor tip.
+    ///
     /// Returns the (L2 block info, L1 origin block info) tuple for the new cursor state.
     pub fn reset(&mut self, fork_block: u64) -> (BlockInfo, BlockInfo) {
-        let channel_start = fork_block - self.channel_timeout;
-
-        match self.l1_origin_to_l2_blocks.get(&channel_start) {
-            Some(l2_safe_tip) => {
-                // The channel start block is in the cache, we can use it to reset the cursor.
-                (l2_safe_tip.block_info, self.l1_origin_block_info[&channel_start])
-            }
-            None => {
-                // If the channel start block is not in the cache, we reset the cursor
-                // to the closest known L1 block for which we have a corresponding L2 block.
-                let (last_l1_known_tip, l2_known_tip) = self
-                    .l1_origin_to_l2_blocks
-                    .range(..=channel_start)
-                    .next_back()
-                    .expect("walked back to genesis without finding anchor origin block");
-
-                (l2_known_tip.block_info, self.l1_origin_block_info[last_l1_known_tip])
-            }
+        let channel_start = fork_block.saturating_sub(self.channel_timeout);
+
+        // If the channel start block is not in the cache, we reset the cursor
+        // to the closest known L1 block for which we have a corresponding L2 block.
+        let (last_l1_known_tip, l2_known_tip) = self
+            .l1_origin_to_l2_blocks
+            .range(..=channel_start)
+            .next_back()
+            .map(|(l1, l2)| (*l1, *l2))
+            .expect("walked back to genesis without finding anchor origin block");
+
+        self.l1_origin_to_l2_blocks.split_off(&(last_l1_known_tip + 1));
+        self.l1_origin_key_order.retain(|n| *n <= last_l1_known_tip);
+        self.l1_origin_block_info.retain(|n, _| *n <= last_l1_known_tip);
+
+        // NOTE: the finalized head is never rewound, as finalized L1 blocks cannot be reorged.
+        self.heads.safe_head = l2_known_tip;
+        self.heads.unsafe_head = l2_known_tip;
+
+        (l2_known_tip.block_info, self.l1_origin_block_info[&last_l1_known_tip])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn l1_block(number: u64) -> BlockInfo {
+        BlockInfo { number, ..Default::default() }
+    }
+
+    fn l2_block(number: u64, l1_origin: u64) -> L2BlockInfo {
+        let mut block = L2BlockInfo::default();
+        block.block_info.number = number;
+        block.l1_origin.number = l1_origin;
+        block
+    }
+
+    #[test]
+    fn test_advance_moves_safe_and_unsafe_heads() {
+        let mut cursor = SyncCursor::new(10);
+        cursor.advance(l1_block(1), l2_block(100, 1));
+        cursor.advance(l1_block(2), l2_block(106, 2));
+
+        let heads = cursor.heads();
+        assert_eq!(heads.safe_head, l2_block(106, 2));
+        assert_eq!(heads.unsafe_head, l2_block(106, 2));
+        assert_eq!(heads.finalized_head, L2BlockInfo::default());
+    }
+
+    #[test]
+    fn test_unsafe_head_ahead_of_safe_head() {
+        let mut cursor = SyncCursor::new(10);
+        cursor.advance(l1_block(1), l2_block(100, 1));
+        cursor.update_unsafe_head(l2_block(104, 1));
+
+        // Deriving blocks behind the unsafe head does not move it back.
+        cursor.advance(l1_block(2), l2_block(102, 2));
+        assert_eq!(cursor.heads().safe_head, l2_block(102, 2));
+        assert_eq!(cursor.heads().unsafe_head, l2_block(104, 1));
+    }
+
+    #[test]
+    fn test_finalize_follows_l1_finality() {
+        let mut cursor = SyncCursor::new(10);
+        for i in 1..=5 {
+            cursor.advance(l1_block(i), l2_block(100 + i * 6, i));
         }
+
+        // Nothing is finalized before the first L1 origin.
+        assert_eq!(cursor.finalize(0), None);
+
+        assert_eq!(cursor.finalize(3), Some(l2_block(118, 3)));
+        assert_eq!(cursor.heads().finalized_head, l2_block(118, 3));
+
+        // Finality never moves backwards.
+        assert_eq!(cursor.finalize(2), None);
+        assert_eq!(cursor.heads().finalized_head, l2_block(118, 3));
+
+        // L1 finality ahead of the derived chain finalizes the safe head.
+        assert_eq!(cursor.finalize(100), Some(l2_block(130, 5)));
+    }
+
+    #[test]
+    fn test_restore_from_entries() {
+        let mut cursor = SyncCursor::new(10);
+        for i in 1..=3 {
+            cursor.advance(l1_block(i), l2_block(100 + i, i));
+        }
+        cursor.finalize(2);
+
+        let mut restored = SyncCursor::new(10);
+        restored.restore(cursor.entries(), cursor.heads().finalized_head);
+
+        assert_eq!(restored.entries(), cursor.entries());
+        assert_eq!(restored.heads(), cursor.heads());
+        assert_eq!(restored.tip(), l2_block(103, 3));
+    }
+
+    #[test]
+    fn test_reset_rewinds_heads() {
+        let mut cursor = SyncCursor::new(2);
+        for i in 1..=6 {
+            cursor.advance(l1_block(i), l2_block(100 + i, i));
+        }
+        cursor.finalize(5);
+
+        let (l2_safe_tip, l1_origin) = cursor.reset(5);
+        assert_eq!(l2_safe_tip.number, 103);
+        assert_eq!(l1_origin.number, 3);
+
+        let heads = cursor.heads();
+        assert_eq!(cursor.tip(), l2_block(103, 3));
+        assert_eq!(heads.safe_head, l2_block(103, 3));
+        assert_eq!(heads.unsafe_head, l2_block(103, 3));
+        assert_eq!(heads.finalized_head, l2_block(105, 5));
     }
 }
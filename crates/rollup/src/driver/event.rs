@@ -0,0 +1,24 @@
+//! Events emitted by the rollup driver.
+
+use alloy::primitives::B256;
+use op_alloy_protocol::L2BlockInfo;
+
+/// An event emitted by the [Driver](crate::Driver) when the state of the L2 chain changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriverEvent {
+    /// The unsafe L2 head has been updated.
+    UnsafeHead(L2BlockInfo),
+    /// The safe L2 head has been updated after deriving a new block from L1 data.
+    SafeHead(L2BlockInfo),
+    /// The finalized L2 head has been updated after its L1 origin got finalized.
+    FinalizedHead(L2BlockInfo),
+    /// The output root of a derived L2 block differs from the reference output root.
+    OutputRootMismatch {
+        /// The derived L2 block.
+        block: L2BlockInfo,
+        /// The reference output root.
+        expected: B256,
+        /// The output root computed from the L2 execution client.
+        actual: B256,
+    },
+}
@@ -1,40 +1,78 @@
 //! Rollup Node Driver
 
-use std::{fmt::Debug, sync::Arc};
+use std::{collections::BTreeMap, fmt::Debug, net::SocketAddr, sync::Arc};
 
-use eyre::{bail, eyre, Result};
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::B256,
+    providers::{network::primitives::BlockTransactionsKind, Provider, ReqwestProvider},
+};
+
+use eyre::{bail, eyre, Result, WrapErr};
 use kona_derive::{
     errors::{PipelineError, PipelineErrorKind},
-    online::{AlloyChainProvider, AlloyL2ChainProvider, OnlineBlobProviderBuilder},
-    traits::{BlobProvider, ChainProvider, L2ChainProvider},
+    online::{AlloyChainProvider, AlloyL2ChainProvider},
+    traits::{BlobProvider, ChainProvider, L2ChainProvider, OriginProvider},
 };
+use kona_primitives::L2ExecutionPayloadEnvelope;
 use kona_providers::{
-    blob_provider::DurableBlobProvider, InMemoryChainProvider, LayeredBlobProvider, Pipeline,
-    StepResult,
+    blob_provider::DurableBlobProvider, InMemoryChainProvider, LayeredBlobProvider,
+    LayeredChainProvider, Pipeline, RethChainProvider, StepResult,
 };
+use metrics::counter;
 use op_alloy_genesis::RollupConfig;
 use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use op_net::types::envelope::ExecutionPayloadEnvelope;
 use reth::rpc::types::engine::JwtSecret;
 use reth_exex::ExExContext;
 use reth_node_api::FullNodeComponents;
+use ser::types::{L1BlockRef, SyncStatus};
+use tokio::{
+    sync::{broadcast, mpsc, watch},
+    time::{sleep_until, Instant},
+};
 use tracing::{debug, error, info, trace, warn};
+use url::Url;
 
 use crate::{
-    cli::ValidationMode,
+    cli::{StartBlock, ValidationMode},
+    engine::{EngineClient, EngineController},
     new_rollup_pipeline,
+    output::{OutputReference, OutputVerification, OutputVerifier},
+    rpc::{empty_sync_status, l1_block_ref, l2_block_ref},
     validator::{EngineApiValidator, TrustedValidator},
-    AttributesValidator, HeraArgsExt, RollupPipeline,
+    AttributesValidator, HeraArgsExt, RetryPolicy, RetryingValidator, RollupNodeRpc,
+    RollupPipeline, UnsafePayloadStatus,
 };
 
 mod context;
 use context::{ChainNotification, DriverContext, ExExHeraContext, StandaloneHeraContext};
 
+mod checkpoint;
+use checkpoint::{Checkpoint, CheckpointStore};
+
 mod cursor;
+pub use cursor::L2Heads;
 use cursor::SyncCursor;
 
+mod event;
+pub use event::DriverEvent;
+
+mod policy;
+use policy::{InvalidPayloadHandler, PayloadAction};
+
+#[cfg(test)]
+mod replay;
+
+/// The capacity of the driver event channel.
+const DRIVER_EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// The capacity of the channel bridging unsafe blocks from the network to the driver.
+const UNSAFE_BLOCK_CHANNEL_CAPACITY: usize = 256;
+
 /// The Rollup Driver entrypoint.
 #[derive(Debug)]
-pub struct Driver<DC, CP, BP> {
+pub struct Driver<DC, CP, BP, L2CP = AlloyL2ChainProvider> {
     /// The rollup configuration
     cfg: Arc<RollupConfig>,
     /// The context of the node
@@ -44,50 +82,96 @@ pub struct Driver<DC, CP, BP> {
     /// The L1 blob provider
     blob_provider: BP,
     /// The L2 chain provider
-    l2_chain_provider: AlloyL2ChainProvider,
+    l2_chain_provider: L2CP,
+    /// The L2 execution client provider, used to look up L2 blocks by tag
+    l2_provider: ReqwestProvider,
     /// Cursor to keep track of the L2 tip
     cursor: SyncCursor,
     /// The validator to verify newly derived L2 attributes
     validator: Box<dyn AttributesValidator>,
+    /// Sender for the events emitted by the driver
+    events: broadcast::Sender<DriverEvent>,
+    /// Sender for the sync status published by the driver
+    sync_status: watch::Sender<SyncStatus>,
+    /// The verifier for the output roots of derived blocks, if a reference is set
+    output_verifier: Option<OutputVerifier>,
+    /// The rollup node RPC server and the address to serve it on, if enabled
+    rpc: Option<(SocketAddr, RollupNodeRpc)>,
+    /// The handler for derived attributes that fail validation
+    invalid_payload_handler: InvalidPayloadHandler,
+    /// The retry policy for the RPC calls made by the driver
+    retry: RetryPolicy,
+    /// The on-disk store used to persist the driver state, if a data directory is set
+    checkpoint: Option<CheckpointStore>,
+    /// The L2 block to start deriving from, overriding the checkpoint if set
+    start_block: Option<StartBlock>,
+    /// The last L1 block number reported as processed to the driver context
+    last_processed_l1: u64,
+    /// Receiver for the unsafe blocks gossiped on the p2p network, if enabled
+    unsafe_block_rx: Option<mpsc::Receiver<ExecutionPayloadEnvelope>>,
+    /// The hashes of the unsafe blocks ahead of the safe head, by block number
+    unsafe_blocks: BTreeMap<u64, B256>,
 }
 
-impl<N: FullNodeComponents> Driver<ExExHeraContext<N>, InMemoryChainProvider, LayeredBlobProvider> {
+impl<N: FullNodeComponents>
+    Driver<
+        ExExHeraContext<N>,
+        LayeredChainProvider<RethChainProvider<N::Provider>>,
+        LayeredBlobProvider,
+    >
+{
     /// Create a new Hera Execution Extension Driver
-    pub fn exex(ctx: ExExContext<N>, args: HeraArgsExt, cfg: Arc<RollupConfig>) -> Self {
-        let chain_provider = InMemoryChainProvider::with_capacity(args.l1_chain_cache_size);
+    pub fn exex(ctx: ExExContext<N>, args: HeraArgsExt, cfg: Arc<RollupConfig>) -> Result<Self> {
+        // Recent L1 blocks are served from the in-memory cache, older ones from reth's database.
+        let l1_cache = InMemoryChainProvider::with_capacity(args.l1_chain_cache_size);
+        let chain_provider = LayeredChainProvider::new(
+            l1_cache.clone(),
+            RethChainProvider::new(ctx.provider().clone()),
+        );
         let blob_provider = LayeredBlobProvider::new(
             args.l1_beacon_client_url.clone(),
             args.l1_blob_archiver_url.clone(),
         );
 
         // The ExEx Hera context is responsible for handling notifications from the execution
-        // extension, and will automatically cache L1 blocks as they come in to make them available
-        // to the derivation pipeline's L1 chain provider.
-        let exex_ctx = ExExHeraContext::new(ctx, chain_provider.clone());
+        // extension, and will automatically cache L1 blocks and their blob sidecars as they come in
+        // to make them available to the derivation pipeline's L1 chain and blob providers.
+        let exex_ctx = ExExHeraContext::new(ctx, l1_cache, blob_provider.clone());
 
         Self::with_components(exex_ctx, args, cfg, chain_provider, blob_provider)
     }
 }
 
-impl Driver<StandaloneHeraContext, AlloyChainProvider, DurableBlobProvider> {
+impl Driver<StandaloneHeraContext, LayeredChainProvider<AlloyChainProvider>, DurableBlobProvider> {
     /// Create a new Standalone Hera Driver
     pub async fn standalone(args: HeraArgsExt, cfg: Arc<RollupConfig>) -> Result<Self> {
-        let chain_provider = AlloyChainProvider::new_http(args.l1_rpc_url.clone());
-        let blob_provider = OnlineBlobProviderBuilder::new()
-            .with_primary(args.l1_beacon_client_url.as_str().trim_end_matches('/').to_string())
-            .with_fallback(
-                args.l1_blob_archiver_url
-                    .clone()
-                    .map(|url| url.as_str().trim_end_matches('/').to_string()),
-            )
-            .build();
+        // L1 blocks fetched over RPC are cached in memory, and invalidated on L1 reorgs.
+        let l1_cache = InMemoryChainProvider::with_capacity(args.l1_chain_cache_size);
+        let chain_provider = LayeredChainProvider::new(
+            l1_cache.clone(),
+            AlloyChainProvider::new_http(args.l1_rpc_url.clone()),
+        );
+        let blob_provider = DurableBlobProvider::new(
+            args.l1_beacon_client_url.clone(),
+            args.l1_blob_archiver_url.clone(),
+        );
 
         // The Standalone Hera context is responsible for handling notifications from the node.
-        // Currently there is no cache layer for L1 data as it is assumed that it will be fetched
-        // from the L1 chain provider directly.
-        let standalone_ctx = StandaloneHeraContext::new(args.l1_rpc_url.clone()).await?;
+        let standalone_ctx = if args.l1_beacon_events {
+            StandaloneHeraContext::with_beacon_events(
+                args.l1_rpc_url.clone(),
+                &args.l1_beacon_client_url,
+            )
+        } else {
+            StandaloneHeraContext::new(args.l1_rpc_url.clone()).await?
+        };
+        let standalone_ctx = standalone_ctx.with_l1_cache(l1_cache);
+        let l1_connection = standalone_ctx.l1_connection();
 
-        Ok(Self::with_components(standalone_ctx, args, cfg, chain_provider, blob_provider))
+        let mut driver =
+            Self::with_components(standalone_ctx, args, cfg, chain_provider, blob_provider)?;
+        driver.rpc = driver.rpc.map(|(addr, rpc)| (addr, rpc.with_l1_connection(l1_connection)));
+        Ok(driver)
     }
 }
 
@@ -98,30 +182,295 @@ where
     BP: BlobProvider + Clone + Send + Sync + Debug + 'static,
 {
     /// Create a new Hera Driver with the provided components.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the arguments are inconsistent, e.g. if the engine API
+    /// settings are missing or invalid, or if the data directory cannot be opened.
     fn with_components(
         ctx: DC,
         args: HeraArgsExt,
         cfg: Arc<RollupConfig>,
         l1_chain_provider: CP,
         blob_provider: BP,
-    ) -> Self {
+    ) -> Result<Self> {
         let cursor = SyncCursor::new(cfg.channel_timeout);
+        let retry = RetryPolicy::default().with_max_attempts(args.rpc_max_attempts);
         let validator: Box<dyn AttributesValidator> = match args.validation_mode {
             ValidationMode::Trusted => Box::new(TrustedValidator::new_http(
                 args.l2_rpc_url.clone(),
                 cfg.canyon_time.unwrap_or(0),
             )),
-            ValidationMode::EngineApi => Box::new(EngineApiValidator::new_http(
-                args.l2_engine_api_url.expect("Missing L2 engine API URL"),
-                match args.l2_engine_jwt_secret.as_ref() {
-                    Some(fpath) => JwtSecret::from_file(fpath).expect("Invalid L2 JWT secret file"),
-                    None => panic!("Missing L2 engine JWT secret"),
-                },
-            )),
+            ValidationMode::EngineApi => {
+                let (url, jwt) = engine_api_auth(&args)?;
+                Box::new(EngineApiValidator::new_http(url, jwt, args.l2_rpc_url.clone()))
+            }
+            ValidationMode::Consensus => {
+                let (url, jwt) = engine_api_auth(&args)?;
+                Box::new(EngineController::new(
+                    EngineClient::new_http(url, jwt, cfg.clone()),
+                    TrustedValidator::new_http(
+                        args.l2_rpc_url.clone(),
+                        cfg.canyon_time.unwrap_or(0),
+                    ),
+                ))
+            }
+        };
+        let validator = Box::new(RetryingValidator::new(validator, retry));
+        let (events, _) = broadcast::channel(DRIVER_EVENT_CHANNEL_CAPACITY);
+        let (sync_status, _) = watch::channel(empty_sync_status());
+        let rpc = args.rpc_addr.map(|addr| {
+            let rpc = RollupNodeRpc::new_http(
+                cfg.clone(),
+                args.l2_rpc_url.clone(),
+                sync_status.subscribe(),
+            );
+            (addr, rpc)
+        });
+        let output_reference = match (args.output_reference_rpc_url, args.l2_output_oracle) {
+            (Some(url), _) => Some(OutputReference::rollup_node(url)),
+            (None, Some(address)) => {
+                Some(OutputReference::l2_output_oracle(args.l1_rpc_url.clone(), address))
+            }
+            (None, None) => None,
         };
+        let output_verifier = output_reference
+            .map(|reference| OutputVerifier::new_http(args.l2_rpc_url.clone(), reference));
+        let l2_provider = ReqwestProvider::new_http(args.l2_rpc_url.clone());
+        let invalid_payload_handler = InvalidPayloadHandler::new(
+            args.on_invalid_payload,
+            args.get_invalid_payload_dump_dir(),
+        )?;
         let l2_chain_provider = AlloyL2ChainProvider::new_http(args.l2_rpc_url, cfg.clone());
+        let checkpoint = args
+            .datadir
+            .map(CheckpointStore::open)
+            .transpose()
+            .wrap_err("Failed to open the checkpoint store")?;
+
+        Ok(Self {
+            cfg,
+            ctx,
+            l1_chain_provider,
+            blob_provider,
+            l2_chain_provider,
+            l2_provider,
+            cursor,
+            validator,
+            events,
+            sync_status,
+            output_verifier,
+            rpc,
+            invalid_payload_handler,
+            retry,
+            checkpoint,
+            start_block: args.start_block,
+            last_processed_l1: 0,
+            unsafe_block_rx: None,
+            unsafe_blocks: BTreeMap::new(),
+        })
+    }
+
+    /// Starts the Hera derivation loop and tries to advance the driver to
+    /// the L2 chain tip.
+    ///
+    /// # Errors
+    ///
+    /// This function should never error. If it does, it means the entire driver
+    /// will shut down. If running as ExEx, the entire L1 node + all other running
+    /// execution extensions will be shutdown as well.
+    pub async fn start(mut self) -> Result<()> {
+        // Step 0: Serve the rollup node RPC, if enabled
+        if let Some((addr, rpc)) = self.rpc.take() {
+            let handle = rpc.start(addr).await?;
+            tokio::spawn(handle.stopped());
+        }
+
+        // Step 1: Wait for the L2 origin block to be available
+        self.wait_for_l2_genesis_l1_block().await?;
+        info!("L1 chain synced to the rollup genesis block");
+
+        // Step 2: Initialize the rollup pipeline
+        let mut pipeline = self.init_pipeline().await?;
+        info!("Derivation pipeline initialized");
+
+        // Step 3: Start the processing loop
+        let mut idle_rounds = 0;
+        let mut idle_until: Option<Instant> = None;
+        loop {
+            // Try to advance the pipeline until there's no more data to process
+            if self.step(&mut pipeline).await? {
+                idle_rounds = 0;
+                continue;
+            }
+
+            // Handle any incoming notifications from the context or unsafe blocks
+            // from the network, whichever comes first.
+            let unsafe_block_rx = &mut self.unsafe_block_rx;
+            let idle_deadline = idle_until.unwrap_or_else(Instant::now);
+            tokio::select! {
+                notification = self.ctx.recv_notification(), if idle_until.is_none() => {
+                    if let Some(notification) = notification {
+                        idle_rounds = 0;
+                        self.handle_notification(notification, &mut pipeline).await?;
+                    } else {
+                        // Avoid busy-looping while the context has nothing to deliver,
+                        // while still handling unsafe blocks in the meantime.
+                        idle_until = Some(Instant::now() + self.retry.backoff(idle_rounds));
+                        idle_rounds = idle_rounds.saturating_add(1);
+                    }
+                }
+                _ = sleep_until(idle_deadline), if idle_until.is_some() => {
+                    idle_until = None;
+                }
+                Some(envelope) = recv_unsafe_block(unsafe_block_rx) => {
+                    self.handle_unsafe_block(envelope).await;
+                }
+            }
+        }
+    }
+}
+
+impl<DC, CP, BP, L2CP> Driver<DC, CP, BP, L2CP>
+where
+    DC: DriverContext,
+    CP: ChainProvider + Clone + Send + Sync + Debug + 'static,
+    BP: BlobProvider + Clone + Send + Sync + Debug + 'static,
+    L2CP: L2ChainProvider + Clone + Send + Sync + Debug + 'static,
+{
+    /// Feed the unsafe blocks received from the p2p network into the driver.
+    ///
+    /// Unsafe blocks are validated as they arrive and advance the unsafe L2 head.
+    /// They are reconciled with the derived chain as the safe head moves forward.
+    pub fn with_unsafe_block_recv(
+        mut self,
+        recv: std::sync::mpsc::Receiver<ExecutionPayloadEnvelope>,
+    ) -> Self {
+        // The network driver uses a blocking channel, so bridge it to an async one
+        // that can be polled alongside the chain notifications.
+        let (tx, rx) = mpsc::channel(UNSAFE_BLOCK_CHANNEL_CAPACITY);
+        tokio::task::spawn_blocking(move || {
+            while let Ok(envelope) = recv.recv() {
+                if tx.blocking_send(envelope).is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.unsafe_block_rx = Some(rx);
+        self
+    }
 
-        Self { cfg, ctx, l1_chain_provider, blob_provider, l2_chain_provider, cursor, validator }
+    /// Subscribe to the [DriverEvent]s emitted by the driver.
+    ///
+    /// This must be called before [`Driver::start`], which consumes the driver.
+    pub fn subscribe(&self) -> broadcast::Receiver<DriverEvent> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe to the [SyncStatus] published by the driver.
+    pub fn sync_status(&self) -> watch::Receiver<SyncStatus> {
+        self.sync_status.subscribe()
+    }
+
+    /// Publish the current L2 heads and the L1 origin of the safe head in the [SyncStatus].
+    fn publish_heads(&self) {
+        let heads = self.cursor.heads();
+        let safe_l1 = self.cursor.l1_origin(heads.safe_head.l1_origin.number);
+        self.sync_status.send_modify(|status| {
+            status.unsafe_l2 = l2_block_ref(&heads.unsafe_head);
+            status.safe_l2 = l2_block_ref(&heads.safe_head);
+            status.pending_safe_l2 = l2_block_ref(&heads.safe_head);
+            status.finalized_l2 = l2_block_ref(&heads.finalized_head);
+            if let Some(safe_l1) = safe_l1 {
+                status.safe_l1 = l1_block_ref(&safe_l1);
+            }
+        });
+    }
+
+    /// Fetch the L1 block info for the given block number, for the [SyncStatus].
+    ///
+    /// This is best-effort: the sync status is not worth retrying for.
+    async fn l1_block_ref(&self, number: u64) -> Option<L1BlockRef> {
+        match self.l1_chain_provider.clone().block_info_by_number(number).await {
+            Ok(block) => Some(l1_block_ref(&block)),
+            Err(err) => {
+                debug!("Failed to fetch L1 block {} for the sync status: {:?}", number, err);
+                None
+            }
+        }
+    }
+
+    /// Emit a [DriverEvent] to all subscribers.
+    fn emit(&self, event: DriverEvent) {
+        // An error here only means that there are no active subscribers.
+        let _ = self.events.send(event);
+    }
+
+    /// Initialize the rollup pipeline from the driver's components.
+    ///
+    /// If a start block is set, the pipeline starts from it. Otherwise, it resumes from
+    /// the checkpoint persisted by a previous run, if any, or starts from the latest L2 block.
+    async fn init_pipeline(&mut self) -> Result<RollupPipeline<CP, BP, L2CP>> {
+        let l1_origin = match self.start_block {
+            Some(start_block) => {
+                if self.checkpoint.is_some() {
+                    info!("Start block set to {}, ignoring the driver checkpoint", start_block);
+                }
+                self.init_cursor(start_block).await?
+            }
+            None => match self.restore_checkpoint().await? {
+                Some(l1_origin) => l1_origin,
+                None => self.init_cursor(StartBlock::Latest).await?,
+            },
+        };
+
+        Ok(new_rollup_pipeline(
+            self.cfg.clone(),
+            self.l1_chain_provider.clone(),
+            self.blob_provider.clone(),
+            self.l2_chain_provider.clone(),
+            l1_origin,
+        ))
+    }
+
+    /// Move the cursor to the given start block.
+    ///
+    /// Returns the L1 origin of the start block, where the pipeline starts from.
+    async fn init_cursor(&mut self, start_block: StartBlock) -> Result<BlockInfo> {
+        let number = self.resolve_start_block(start_block).await?;
+        let (l1_origin, l2_block) = self.fetch_new_tip(number).await?;
+
+        // Advance the cursor to the start block before starting the pipeline
+        self.cursor.advance(l1_origin, l2_block);
+        self.publish_heads();
+
+        info!("Starting derivation from L2 block {} (L1 origin: {})", number, l1_origin.number);
+        Ok(l1_origin)
+    }
+
+    /// Resolve the given start block to an L2 block number.
+    async fn resolve_start_block(&self, start_block: StartBlock) -> Result<u64> {
+        let tag = match start_block {
+            StartBlock::Number(number) => return Ok(number),
+            StartBlock::Genesis => return Ok(self.cfg.genesis.l2.number),
+            StartBlock::Latest => BlockNumberOrTag::Latest,
+            StartBlock::Safe => BlockNumberOrTag::Safe,
+            StartBlock::Finalized => BlockNumberOrTag::Finalized,
+        };
+
+        let block = self
+            .retry
+            .retry("l2_block_by_tag", || async move {
+                self.l2_provider
+                    .get_block(tag.into(), BlockTransactionsKind::Hashes)
+                    .await
+                    .map_err(|e| eyre!(e))?
+                    .ok_or(eyre!("No {} L2 block found", tag))
+            })
+            .await?;
+
+        Ok(block.header.number)
     }
 
     /// Wait for the L2 genesis' corresponding L1 block to be available in the L1 chain.
@@ -131,10 +480,6 @@ where
                 if let Some(new_chain) = notification.new_chain() {
                     let tip = new_chain.tip();
 
-                    if let Err(err) = self.ctx.send_processed_tip_event(tip) {
-                        bail!("Failed to send processed tip event: {:?}", err);
-                    }
-
                     if tip >= self.cfg.genesis.l1.number {
                         break Ok(());
                     } else {
@@ -145,31 +490,100 @@ where
         }
     }
 
-    /// Initialize the rollup pipeline from the driver's components.
-    async fn init_pipeline(&mut self) -> Result<RollupPipeline<CP, BP>> {
-        // Fetch the current L2 tip and its corresponding L1 origin block
-        let l2_tip = self.l2_chain_provider.latest_block_number().await.map_err(|e| eyre!(e))?;
-        let (l2_tip_l1_origin, l2_tip_block_info) = self.fetch_new_tip(l2_tip).await?;
+    /// Restore the cursor from the persisted checkpoint, if any.
+    ///
+    /// Returns the L1 origin block of the restored safe head, or `None` if there is no
+    /// usable checkpoint (e.g. if its safe head is no longer part of the L2 chain).
+    async fn restore_checkpoint(&mut self) -> Result<Option<BlockInfo>> {
+        let Some(store) = &self.checkpoint else {
+            return Ok(None);
+        };
+        let Some(checkpoint) = store.load()? else {
+            info!("No driver checkpoint found, starting from the latest L2 block");
+            return Ok(None);
+        };
 
-        // Advance the cursor to the L2 tip before starting the pipeline
-        self.cursor.advance(l2_tip_l1_origin, l2_tip_block_info);
+        let safe_head = checkpoint.safe_head;
+        let (l1_origin, l2_block) = self.fetch_new_tip(safe_head.block_info.number).await?;
+        if l2_block.block_info.hash != safe_head.block_info.hash {
+            warn!(
+                "Checkpoint safe head {} is no longer canonical, starting from the latest L2 block",
+                safe_head.block_info.number
+            );
+            return Ok(None);
+        }
 
-        Ok(new_rollup_pipeline(
-            self.cfg.clone(),
-            self.l1_chain_provider.clone(),
-            self.blob_provider.clone(),
-            self.l2_chain_provider.clone(),
-            l2_tip_l1_origin,
-        ))
+        self.cursor.restore(checkpoint.l1_origin_to_l2_tip, checkpoint.finalized_head);
+        self.last_processed_l1 = checkpoint.last_processed_l1;
+        self.publish_heads();
+        info!("Resuming derivation from checkpoint at L2 block: {}", safe_head.block_info.number);
+        Ok(Some(l1_origin))
+    }
+
+    /// Persist the current driver state to the checkpoint store, if any.
+    fn persist_checkpoint(&self) {
+        let Some(store) = &self.checkpoint else {
+            return;
+        };
+
+        let heads = self.cursor.heads();
+        let checkpoint = Checkpoint::new(
+            self.cursor.entries(),
+            heads.safe_head,
+            heads.finalized_head,
+            self.last_processed_l1,
+        );
+        if let Err(err) = store.save(&checkpoint) {
+            error!("Failed to persist driver checkpoint: {:?}", err);
+        }
+    }
+
+    /// Report the L1 blocks that derivation no longer needs to the driver context,
+    /// allowing the L1 node to prune them.
+    ///
+    /// This follows the L1 origin of the pipeline minus the channel timeout, as frames
+    /// of channels opened up to that many blocks before the origin may still be read.
+    /// It is capped at the L1 origin of the safe head, which is checkpointed whenever
+    /// that origin changes: resets restart derivation from the safe head, so the blocks
+    /// between its L1 origin and the pipeline origin may be read again.
+    /// The reported height never moves backwards, even if the pipeline is reset.
+    fn report_processed_l1(&mut self, origin: u64) -> Result<()> {
+        let safe_origin = self.cursor.heads().safe_head.l1_origin.number;
+        let processed = origin.min(safe_origin).saturating_sub(self.cfg.channel_timeout);
+        if processed <= self.last_processed_l1 {
+            return Ok(());
+        }
+
+        if let Err(err) = self.ctx.send_processed_tip_event(processed) {
+            bail!("Failed to send processed tip event: {:?}", err);
+        }
+        self.last_processed_l1 = processed;
+        Ok(())
     }
 
     /// Advance the pipeline to the next L2 block.
     ///
     /// Returns `true` if the pipeline can move forward again, `false` otherwise.
-    async fn step(&mut self, pipeline: &mut RollupPipeline<CP, BP>) -> bool {
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the driver must halt, e.g. when derived attributes
+    /// are invalid and the invalid payload policy is set to halt.
+    async fn step<P: Pipeline + Send>(&mut self, pipeline: &mut P) -> Result<bool> {
         let l2_tip = self.cursor.tip();
 
-        match pipeline.step(l2_tip).await {
+        let step = pipeline.step(l2_tip).await;
+        if let Some(origin) = pipeline.origin() {
+            self.sync_status.send_if_modified(|status| {
+                let current_l1 = l1_block_ref(&origin);
+                let modified = status.current_l1 != current_l1;
+                status.current_l1 = current_l1;
+                modified
+            });
+            self.report_processed_l1(origin.number)?;
+        }
+
+        match step {
             StepResult::PreparedAttributes => trace!("Prepared new attributes"),
             StepResult::AdvancedOrigin => trace!("Advanced origin"),
             StepResult::OriginAdvanceErr(err) => warn!("Could not advance origin: {:?}", err),
@@ -182,68 +596,232 @@ where
             },
         }
 
-        let derived_attributes = if let Some(attributes) = pipeline.peek() {
-            match self.validator.validate(attributes).await {
-                Ok(true) => {
+        let Some(attributes) = pipeline.peek() else {
+            debug!("No attributes available to validate");
+            return Ok(false);
+        };
+
+        let derived_attributes =
+            match self.invalid_payload_handler.validate(self.validator.as_ref(), attributes).await?
+            {
+                PayloadAction::Accept => {
                     trace!("Validated payload attributes");
                     pipeline.next().expect("Peeked attributes must be available")
                 }
-                Ok(false) => {
-                    error!("Failed payload attributes validation");
-                    // TODO: allow users to specify how they want to treat invalid payloads.
-                    // In the default scenario we just log an error and continue.
-                    return false;
+                PayloadAction::Skip(deposits_only) => {
+                    // The invalid block is replaced by its deposit-only version,
+                    // which passed validation and becomes the new safe head.
+                    pipeline.next();
+                    *deposits_only
                 }
-                Err(err) => {
-                    error!("Error while validating payload attributes: {:?}", err);
-                    return false;
+                PayloadAction::Reset => {
+                    self.reset_to_safe_head(pipeline).await?;
+                    return Ok(false);
                 }
-            }
-        } else {
-            debug!("No attributes available to validate");
-            return false;
-        };
+                PayloadAction::Wait => return Ok(false),
+            };
 
         let derived = derived_attributes.parent.block_info.number + 1;
         let (new_l1_origin, new_l2_tip) = match self.fetch_new_tip(derived).await {
             Ok(tip_info) => tip_info,
             Err(err) => {
-                // TODO: add a retry mechanism?
                 error!("Failed to fetch new tip: {:?}", err);
-                return false;
+                return Ok(false);
             }
         };
 
         // Perform a sanity check on the new tip
         if new_l2_tip.block_info.number != derived {
             error!("Expected L2 block number {} but got {}", derived, new_l2_tip.block_info.number);
-            return false;
+            return Ok(false);
         }
 
-        // Advance the cursor to the new L2 block
+        // Advance the cursor to the new L2 block, persisting the driver
+        // state every time the L1 origin of the safe head changes.
+        let origin_changed = self.cursor.tip().l1_origin.number != new_l2_tip.l1_origin.number;
         self.cursor.advance(new_l1_origin, new_l2_tip);
+        self.reconcile_unsafe_blocks(new_l2_tip);
+        if origin_changed {
+            self.persist_checkpoint();
+        }
         info!("Advanced derivation pipeline to L2 block: {}", derived);
-        true
+        self.publish_heads();
+        self.verify_output(new_l2_tip).await;
+        self.emit(DriverEvent::SafeHead(new_l2_tip));
+        if self.cursor.heads().unsafe_head == new_l2_tip {
+            self.emit(DriverEvent::UnsafeHead(new_l2_tip));
+        }
+        Ok(true)
+    }
+
+    /// Verify the output root of a derived block against the configured reference, if any.
+    ///
+    /// Verification failures never halt the driver: mismatches are reported
+    /// with a [DriverEvent::OutputRootMismatch] event.
+    async fn verify_output(&mut self, block: L2BlockInfo) {
+        let Some(verifier) = &mut self.output_verifier else {
+            return;
+        };
+
+        let number = block.block_info.number;
+        match verifier.verify(number).await {
+            Ok(OutputVerification::Match(output_root)) => {
+                counter!("hera_output_roots_total", "status" => "match").increment(1);
+                debug!(%output_root, "Verified output root of L2 block {}", number);
+            }
+            Ok(OutputVerification::Mismatch { expected, actual }) => {
+                counter!("hera_output_roots_total", "status" => "mismatch").increment(1);
+                error!(%expected, %actual, "Output root mismatch for L2 block {}", number);
+                self.emit(DriverEvent::OutputRootMismatch { block, expected, actual });
+            }
+            Ok(OutputVerification::Unavailable(output_root)) => {
+                trace!(%output_root, "No reference output root for L2 block {}", number);
+            }
+            Err(err) => {
+                counter!("hera_output_roots_total", "status" => "error").increment(1);
+                warn!("Failed to verify output root of L2 block {}: {:?}", number, err);
+            }
+        }
+    }
+
+    /// Reconcile the unsafe chain with the new safe head.
+    ///
+    /// If the derived block conflicts with the unsafe block at the same height, the
+    /// unsafe chain is dropped and the unsafe head is moved back to the safe head.
+    fn reconcile_unsafe_blocks(&mut self, safe_head: L2BlockInfo) {
+        let number = safe_head.block_info.number;
+        if let Some(unsafe_hash) = self.unsafe_blocks.get(&number) {
+            if *unsafe_hash != safe_head.block_info.hash {
+                warn!(
+                    %unsafe_hash,
+                    safe_hash = %safe_head.block_info.hash,
+                    "Derived block conflicts with unsafe block {}, dropping unsafe chain",
+                    number
+                );
+                counter!("hera_unsafe_reorgs_total").increment(1);
+                self.unsafe_blocks.clear();
+                self.cursor.update_unsafe_head(safe_head);
+            }
+        }
+
+        // Unsafe blocks at or below the safe head are now part of the derived chain.
+        self.unsafe_blocks = self.unsafe_blocks.split_off(&(number + 1));
+    }
+
+    /// Handle an unsafe block received from the p2p network.
+    ///
+    /// The block is validated and, if valid, becomes the new unsafe L2 head.
+    async fn handle_unsafe_block(&mut self, envelope: ExecutionPayloadEnvelope) {
+        let number = envelope.payload.block_number;
+        if number <= self.cursor.heads().safe_head.block_info.number {
+            trace!("Ignoring unsafe block {} behind the safe head", number);
+            return;
+        }
+
+        match self.validator.validate_unsafe_payload(&envelope).await {
+            Ok(UnsafePayloadStatus::Valid) => {
+                counter!("hera_unsafe_blocks_total", "status" => "valid").increment(1);
+            }
+            Ok(UnsafePayloadStatus::Invalid) => {
+                counter!("hera_unsafe_blocks_total", "status" => "invalid").increment(1);
+                warn!("Rejected invalid unsafe block {}", number);
+                return;
+            }
+            Ok(UnsafePayloadStatus::Unknown) => {
+                counter!("hera_unsafe_blocks_total", "status" => "unknown").increment(1);
+                debug!("Unsafe block {} cannot be validated yet", number);
+                return;
+            }
+            Err(err) => {
+                counter!("hera_unsafe_blocks_total", "status" => "error").increment(1);
+                error!("Failed to validate unsafe block {}: {:?}", number, err);
+                return;
+            }
+        }
+
+        let unsafe_head = match unsafe_block_info(&self.cfg, &envelope) {
+            Ok(block) => block,
+            Err(err) => {
+                error!("Failed to decode unsafe block {}: {:?}", number, err);
+                return;
+            }
+        };
+
+        // A new unsafe block replaces any unsafe block at the same height or above.
+        self.unsafe_blocks.retain(|unsafe_number, _| *unsafe_number < number);
+        self.unsafe_blocks.insert(number, envelope.payload.block_hash);
+        self.cursor.update_unsafe_head(unsafe_head);
+        self.publish_heads();
+        debug!("Advanced unsafe head to L2 block: {}", number);
+        self.emit(DriverEvent::UnsafeHead(unsafe_head));
+    }
+
+    /// Reset the pipeline to the current safe L2 head, discarding any pending attributes.
+    async fn reset_to_safe_head<P: Pipeline + Send>(&mut self, pipeline: &mut P) -> Result<()> {
+        let safe_head = self.cursor.heads().safe_head;
+        let (l1_origin, _) = self.fetch_new_tip(safe_head.block_info.number).await?;
+
+        warn!("Resetting derivation pipeline to L2 block: {}", safe_head.block_info.number);
+        if let Err(e) = pipeline.reset(safe_head.block_info, l1_origin).await {
+            bail!("Failed to reset pipeline: {:?}", e);
+        }
+
+        Ok(())
     }
 
     /// Fetch the new L2 tip and L1 origin block info for the given L2 block number.
+    ///
+    /// Transient RPC errors are retried according to the driver's [RetryPolicy].
     async fn fetch_new_tip(&mut self, l2_tip: u64) -> Result<(BlockInfo, L2BlockInfo)> {
-        let l2_block = self.l2_chain_provider.l2_block_info_by_number(l2_tip).await?;
+        let l2_block = self
+            .retry
+            .retry("l2_block_info_by_number", || {
+                let mut l2_chain_provider = self.l2_chain_provider.clone();
+                async move {
+                    l2_chain_provider
+                        .l2_block_info_by_number(l2_tip)
+                        .await
+                        .map_err(|e| eyre!(e.to_string()))
+                }
+            })
+            .await?;
 
         let l1_origin = self
-            .l1_chain_provider
-            .block_info_by_number(l2_block.l1_origin.number)
-            .await
-            .map_err(|e| eyre!(e.to_string()))?;
+            .retry
+            .retry("l1_block_info_by_number", || {
+                let mut l1_chain_provider = self.l1_chain_provider.clone();
+                async move {
+                    l1_chain_provider
+                        .block_info_by_number(l2_block.l1_origin.number)
+                        .await
+                        .map_err(|e| eyre!(e.to_string()))
+                }
+            })
+            .await?;
 
         Ok((l1_origin, l2_block))
     }
 
+    /// Advance the finalized L2 head after the given L1 block has been finalized.
+    async fn finalize(&mut self, l1_finalized: u64) {
+        let Some(finalized_head) = self.cursor.finalize(l1_finalized) else {
+            return;
+        };
+
+        info!("Finalized L2 block: {}", finalized_head.block_info.number);
+        self.publish_heads();
+        if let Err(err) = self.validator.finalize(&finalized_head).await {
+            error!("Failed to notify the validator of the finalized head: {:?}", err);
+        }
+        self.emit(DriverEvent::FinalizedHead(finalized_head));
+        self.persist_checkpoint();
+    }
+
     /// Handle a chain notification from the driver context.
-    async fn handle_notification(
+    async fn handle_notification<P: Pipeline + Send>(
         &mut self,
         notification: ChainNotification,
-        pipeline: &mut RollupPipeline<CP, BP>,
+        pipeline: &mut P,
     ) -> Result<()> {
         if let Some(reverted_chain) = notification.reverted_chain() {
             // The reverted chain contains the list of blocks that were invalidated by the
@@ -259,47 +837,75 @@ where
             if let Err(e) = pipeline.reset(l2_safe_tip, l2_safe_tip_l1_origin).await {
                 bail!("Failed to reset pipeline: {:?}", e);
             }
+
+            self.unsafe_blocks.clear();
+            self.publish_heads();
+            let heads = self.cursor.heads();
+            self.emit(DriverEvent::UnsafeHead(heads.unsafe_head));
+            self.emit(DriverEvent::SafeHead(heads.safe_head));
+            self.persist_checkpoint();
+        }
+
+        if let Some(l1_finalized) = notification.finalized_block() {
+            if let Some(finalized_l1) = self.l1_block_ref(l1_finalized).await {
+                self.sync_status.send_modify(|status| {
+                    status.finalized_l1 = finalized_l1;
+                    status.current_l1_finalized = finalized_l1;
+                });
+            }
+            self.finalize(l1_finalized).await;
         }
 
         if let Some(new_chain) = notification.new_chain() {
+            // The processed tip is reported as derivation moves forward, not when the
+            // new blocks are received, see `report_processed_l1`.
             let tip = new_chain.tip();
-
-            if let Err(err) = self.ctx.send_processed_tip_event(tip) {
-                bail!("Failed to send processed tip event: {:?}", err);
+            if let Some(head_l1) = self.l1_block_ref(tip).await {
+                self.sync_status.send_modify(|status| status.head_l1 = head_l1);
             }
         }
 
         Ok(())
     }
+}
 
-    /// Starts the Hera derivation loop and tries to advance the driver to
-    /// the L2 chain tip.
-    ///
-    /// # Errors
-    ///
-    /// This function should never error. If it does, it means the entire driver
-    /// will shut down. If running as ExEx, the entire L1 node + all other running
-    /// execution extensions will be shutdown as well.
-    pub async fn start(mut self) -> Result<()> {
-        // Step 1: Wait for the L2 origin block to be available
-        self.wait_for_l2_genesis_l1_block().await?;
-        info!("L1 chain synced to the rollup genesis block");
-
-        // Step 2: Initialize the rollup pipeline
-        let mut pipeline = self.init_pipeline().await?;
-        info!("Derivation pipeline initialized");
+/// Returns the URL and the JWT secret of the L2 engine API from the given arguments.
+fn engine_api_auth(args: &HeraArgsExt) -> Result<(Url, JwtSecret)> {
+    let url = args.l2_engine_api_url.clone().ok_or(eyre!("Missing L2 engine API URL"))?;
+    let Some(path) = args.l2_engine_jwt_secret.as_ref() else {
+        bail!("Missing L2 engine JWT secret");
+    };
+    let jwt = JwtSecret::from_file(path).wrap_err("Invalid L2 engine JWT secret file")?;
+    Ok((url, jwt))
+}
 
-        // Step 3: Start the processing loop
-        loop {
-            // Try to advance the pipeline until there's no more data to process
-            if self.step(&mut pipeline).await {
-                continue;
-            }
+/// Returns the [L2BlockInfo] of an unsafe block from its execution payload,
+/// reading its L1 origin from the L1 info deposit transaction.
+fn unsafe_block_info(
+    cfg: &RollupConfig,
+    envelope: &ExecutionPayloadEnvelope,
+) -> Result<L2BlockInfo> {
+    let envelope = L2ExecutionPayloadEnvelope {
+        execution_payload: envelope.payload.clone(),
+        parent_beacon_block_root: envelope.parent_beacon_block_root,
+    };
+    envelope.to_l2_block_ref(cfg).map_err(|e| eyre!(e.to_string()))
+}
 
-            // Handle any incoming notifications from the context
-            if let Some(notification) = self.ctx.recv_notification().await {
-                self.handle_notification(notification, &mut pipeline).await?;
-            }
-        }
+/// Receive the next unsafe block, if the driver is connected to the p2p network.
+///
+/// Resolves to `None` once the network stops, and never resolves if it is not enabled.
+async fn recv_unsafe_block(
+    rx: &mut Option<mpsc::Receiver<ExecutionPayloadEnvelope>>,
+) -> Option<ExecutionPayloadEnvelope> {
+    let Some(inner) = rx else {
+        return std::future::pending().await;
+    };
+
+    let envelope = inner.recv().await;
+    if envelope.is_none() {
+        warn!("Unsafe block channel closed, no longer following the p2p network");
+        *rx = None;
     }
+    envelope
 }
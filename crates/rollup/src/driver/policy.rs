@@ -0,0 +1,367 @@
+//! Handling of payload attributes that fail validation.
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use eyre::{bail, Context, Result};
+use metrics::counter;
+use op_alloy_rpc_types_engine::OptimismAttributesWithParent;
+use serde_json::json;
+use tracing::{error, warn};
+
+use crate::{cli::InvalidPayloadPolicy, AttributesValidator};
+
+/// The maximum number of pipeline resets for invalid attributes of the same L2 block.
+///
+/// A reset re-derives the same attributes from the same L1 data, so a block that keeps
+/// failing validation after a few resets would otherwise reset the pipeline forever.
+const MAX_RESETS_PER_BLOCK: u32 = 3;
+
+/// The EIP-2718 type of deposit transactions.
+const DEPOSIT_TX_TYPE: u8 = 0x7E;
+
+/// The action the driver should take after validating a set of derived attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadAction {
+    /// The attributes are valid: consume them and advance the cursor.
+    Accept,
+    /// The attributes are invalid: consume them and advance the cursor with the given
+    /// deposit-only attributes instead, which passed validation.
+    Skip(Box<OptimismAttributesWithParent>),
+    /// The attributes are invalid: reset the pipeline to the last safe block.
+    Reset,
+    /// The attributes could not be validated: keep them and try again later.
+    Wait,
+}
+
+/// Applies the configured [InvalidPayloadPolicy] to derived attributes that fail validation.
+#[derive(Debug, Clone)]
+pub struct InvalidPayloadHandler {
+    /// The policy to apply to invalid payloads.
+    policy: InvalidPayloadPolicy,
+    /// The directory where diagnostic bundles are written, if any.
+    dump_dir: Option<PathBuf>,
+    /// The L2 block number of the last invalid attributes that reset the pipeline,
+    /// with the number of resets for that block.
+    resets: Option<(u64, u32)>,
+}
+
+impl InvalidPayloadHandler {
+    /// Creates a new [InvalidPayloadHandler] with the given policy and dump directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the policy is set to dump without a dump directory.
+    pub fn new(policy: InvalidPayloadPolicy, dump_dir: Option<PathBuf>) -> Result<Self> {
+        if policy == InvalidPayloadPolicy::Dump && dump_dir.is_none() {
+            bail!("The dump invalid payload policy requires a dump directory or a data directory")
+        }
+        Ok(Self { policy, dump_dir, resets: None })
+    }
+
+    /// Validates the given attributes and returns the [PayloadAction] the driver should take.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the attributes are invalid and the policy is set to halt,
+    /// if the same block was already reset [MAX_RESETS_PER_BLOCK] times, if the
+    /// deposit-only replacement of a skipped block is invalid too, or if the
+    /// diagnostic bundle could not be written.
+    pub async fn validate(
+        &mut self,
+        validator: &dyn AttributesValidator,
+        attributes: &OptimismAttributesWithParent,
+    ) -> Result<PayloadAction> {
+        match validator.validate(attributes).await {
+            Ok(true) => {
+                self.resets = None;
+                Ok(PayloadAction::Accept)
+            }
+            Ok(false) => self.handle_invalid(validator, attributes).await,
+            Err(err) => {
+                error!("Error while validating payload attributes: {:?}", err);
+                Ok(PayloadAction::Wait)
+            }
+        }
+    }
+
+    /// Handles attributes that failed validation according to the configured policy.
+    async fn handle_invalid(
+        &mut self,
+        validator: &dyn AttributesValidator,
+        attributes: &OptimismAttributesWithParent,
+    ) -> Result<PayloadAction> {
+        let block_number = attributes.parent.block_info.number + 1;
+        error!(policy = %self.policy, "Failed payload attributes validation for L2 block {}", block_number);
+        counter!("hera_invalid_payloads_total", "policy" => self.policy.to_string()).increment(1);
+
+        match self.policy {
+            InvalidPayloadPolicy::Retry => Ok(PayloadAction::Wait),
+            InvalidPayloadPolicy::Halt => {
+                bail!("Invalid payload attributes for L2 block {}", block_number)
+            }
+            InvalidPayloadPolicy::Skip => self.skip(validator, attributes).await,
+            InvalidPayloadPolicy::Reset => {
+                let resets = match self.resets {
+                    Some((number, resets)) if number == block_number => resets + 1,
+                    _ => 1,
+                };
+                if resets > MAX_RESETS_PER_BLOCK {
+                    bail!(
+                        "Invalid payload attributes for L2 block {} after {} pipeline resets",
+                        block_number,
+                        MAX_RESETS_PER_BLOCK
+                    )
+                }
+                self.resets = Some((block_number, resets));
+                warn!("Resetting pipeline after invalid payload for L2 block {}", block_number);
+                Ok(PayloadAction::Reset)
+            }
+            InvalidPayloadPolicy::Dump => {
+                let path = self.dump(attributes)?;
+                warn!(?path, "Dumped invalid payload attributes for L2 block {}", block_number);
+                self.skip(validator, attributes).await
+            }
+        }
+    }
+
+    /// Skips the transactions of invalid attributes, replacing them with their
+    /// deposit-only version like op-node does, so that derivation moves on.
+    async fn skip(
+        &self,
+        validator: &dyn AttributesValidator,
+        attributes: &OptimismAttributesWithParent,
+    ) -> Result<PayloadAction> {
+        let block_number = attributes.parent.block_info.number + 1;
+        let deposits_only = deposits_only(attributes);
+        match validator.validate(&deposits_only).await {
+            Ok(true) => {
+                warn!(
+                    "Replaced invalid payload attributes for L2 block {} with deposits only",
+                    block_number
+                );
+                Ok(PayloadAction::Skip(Box::new(deposits_only)))
+            }
+            Ok(false) => {
+                bail!("Deposit-only payload attributes for L2 block {} are invalid", block_number)
+            }
+            Err(err) => {
+                error!("Error while validating deposit-only payload attributes: {:?}", err);
+                Ok(PayloadAction::Wait)
+            }
+        }
+    }
+
+    /// Writes a diagnostic bundle with the invalid attributes to the dump directory.
+    ///
+    /// Returns the path of the written file.
+    fn dump(&self, attributes: &OptimismAttributesWithParent) -> Result<PathBuf> {
+        let block_number = attributes.parent.block_info.number + 1;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+
+        let bundle = json!({
+            "block_number": block_number,
+            "parent": {
+                "number": attributes.parent.block_info.number,
+                "hash": attributes.parent.block_info.hash,
+                "timestamp": attributes.parent.block_info.timestamp,
+                "l1_origin": {
+                    "number": attributes.parent.l1_origin.number,
+                    "hash": attributes.parent.l1_origin.hash,
+                },
+                "sequence_number": attributes.parent.seq_num,
+            },
+            "is_last_in_span": attributes.is_last_in_span,
+            "attributes": attributes.attributes,
+        });
+
+        let Some(dump_dir) = &self.dump_dir else {
+            bail!("No dump directory for invalid payloads");
+        };
+        fs::create_dir_all(dump_dir).wrap_err("Failed to create dump directory")?;
+        let path = dump_dir.join(format!("invalid-payload-{}-{}.json", block_number, timestamp));
+        fs::write(&path, serde_json::to_vec_pretty(&bundle)?)
+            .wrap_err("Failed to write invalid payload dump")?;
+
+        Ok(path)
+    }
+}
+
+/// Returns the deposit-only version of the given attributes, with all the
+/// transactions but the deposits dropped.
+fn deposits_only(attributes: &OptimismAttributesWithParent) -> OptimismAttributesWithParent {
+    let mut deposits_only = attributes.clone();
+    let transactions = attributes.attributes.transactions.iter().flatten();
+    deposits_only.attributes.transactions =
+        Some(transactions.filter(|tx| tx.first() == Some(&DEPOSIT_TX_TYPE)).cloned().collect());
+    deposits_only.attributes.no_tx_pool = Some(true);
+    deposits_only
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::Bytes;
+    use async_trait::async_trait;
+    use eyre::eyre;
+    use op_alloy_protocol::L2BlockInfo;
+
+    /// A validator that always returns the same result.
+    #[derive(Debug)]
+    struct MockValidator(Option<bool>);
+
+    #[async_trait]
+    impl AttributesValidator for MockValidator {
+        async fn validate(&self, _: &OptimismAttributesWithParent) -> Result<bool> {
+            self.0.ok_or(eyre!("validator unavailable"))
+        }
+    }
+
+    /// A validator that only accepts attributes without user transactions.
+    #[derive(Debug)]
+    struct DepositsOnlyValidator;
+
+    #[async_trait]
+    impl AttributesValidator for DepositsOnlyValidator {
+        async fn validate(&self, attributes: &OptimismAttributesWithParent) -> Result<bool> {
+            let mut transactions = attributes.attributes.transactions.iter().flatten();
+            Ok(transactions.all(|tx| tx.first() == Some(&DEPOSIT_TX_TYPE)))
+        }
+    }
+
+    fn dump_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hera-test-{}-{}", name, std::process::id()))
+    }
+
+    fn handler(policy: InvalidPayloadPolicy, name: &str) -> InvalidPayloadHandler {
+        InvalidPayloadHandler::new(policy, Some(dump_dir(name))).unwrap()
+    }
+
+    fn attributes() -> OptimismAttributesWithParent {
+        let mut parent = L2BlockInfo::default();
+        parent.block_info.number = 41;
+        let mut attributes = OptimismAttributesWithParent::new(Default::default(), parent, false);
+        attributes.attributes.transactions = Some(vec![
+            Bytes::from_static(&[DEPOSIT_TX_TYPE, 0x01]),
+            Bytes::from_static(&[0x02, 0x01]),
+        ]);
+        attributes
+    }
+
+    #[test]
+    fn test_dump_policy_requires_dump_dir() {
+        assert!(InvalidPayloadHandler::new(InvalidPayloadPolicy::Dump, None).is_err());
+        assert!(InvalidPayloadHandler::new(InvalidPayloadPolicy::Skip, None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_valid_attributes_are_accepted() {
+        let mut handler = handler(InvalidPayloadPolicy::Halt, "valid");
+        let action = handler.validate(&MockValidator(Some(true)), &attributes()).await.unwrap();
+        assert_eq!(action, PayloadAction::Accept);
+    }
+
+    #[tokio::test]
+    async fn test_validation_error_waits() {
+        let mut handler = handler(InvalidPayloadPolicy::Halt, "error");
+        let action = handler.validate(&MockValidator(None), &attributes()).await.unwrap();
+        assert_eq!(action, PayloadAction::Wait);
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy() {
+        let mut handler = handler(InvalidPayloadPolicy::Retry, "retry");
+        let action = handler.validate(&MockValidator(Some(false)), &attributes()).await.unwrap();
+        assert_eq!(action, PayloadAction::Wait);
+    }
+
+    #[tokio::test]
+    async fn test_halt_policy() {
+        let mut handler = handler(InvalidPayloadPolicy::Halt, "halt");
+        let result = handler.validate(&MockValidator(Some(false)), &attributes()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_skip_policy_replaces_block_with_deposits_only() {
+        let mut handler = handler(InvalidPayloadPolicy::Skip, "skip");
+        let action = handler.validate(&DepositsOnlyValidator, &attributes()).await.unwrap();
+
+        let PayloadAction::Skip(replacement) = action else {
+            panic!("expected a deposit-only replacement, got {:?}", action);
+        };
+        assert_eq!(
+            replacement.attributes.transactions,
+            Some(vec![Bytes::from_static(&[DEPOSIT_TX_TYPE, 0x01])])
+        );
+        assert_eq!(replacement.attributes.no_tx_pool, Some(true));
+        assert_eq!(replacement.parent, attributes().parent);
+    }
+
+    #[tokio::test]
+    async fn test_skip_policy_halts_on_invalid_deposits_only_block() {
+        let mut handler = handler(InvalidPayloadPolicy::Skip, "skip-invalid");
+        let result = handler.validate(&MockValidator(Some(false)), &attributes()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reset_policy() {
+        let mut handler = handler(InvalidPayloadPolicy::Reset, "reset");
+        let action = handler.validate(&MockValidator(Some(false)), &attributes()).await.unwrap();
+        assert_eq!(action, PayloadAction::Reset);
+    }
+
+    #[tokio::test]
+    async fn test_reset_policy_halts_on_repeated_invalid_block() {
+        let mut handler = handler(InvalidPayloadPolicy::Reset, "reset-loop");
+        let validator = MockValidator(Some(false));
+
+        // Each invalid result for the same block resets the pipeline, until the
+        // reset limit is reached and the driver halts.
+        for _ in 0..MAX_RESETS_PER_BLOCK {
+            let action = handler.validate(&validator, &attributes()).await.unwrap();
+            assert_eq!(action, PayloadAction::Reset);
+        }
+        assert!(handler.validate(&validator, &attributes()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reset_count_is_per_block() {
+        let mut handler = handler(InvalidPayloadPolicy::Reset, "reset-count");
+        let validator = MockValidator(Some(false));
+        for _ in 0..MAX_RESETS_PER_BLOCK {
+            handler.validate(&validator, &attributes()).await.unwrap();
+        }
+
+        // An invalid result for another block starts a new count
+        let mut next = attributes();
+        next.parent.block_info.number += 1;
+        let action = handler.validate(&validator, &next).await.unwrap();
+        assert_eq!(action, PayloadAction::Reset);
+
+        // A valid result clears the count
+        handler.validate(&MockValidator(Some(true)), &next).await.unwrap();
+        let action = handler.validate(&validator, &next).await.unwrap();
+        assert_eq!(action, PayloadAction::Reset);
+    }
+
+    #[tokio::test]
+    async fn test_dump_policy() {
+        let dir = dump_dir("dump");
+        let mut handler = handler(InvalidPayloadPolicy::Dump, "dump");
+        let action = handler.validate(&DepositsOnlyValidator, &attributes()).await.unwrap();
+        assert!(matches!(action, PayloadAction::Skip(_)));
+
+        let dumps = fs::read_dir(&dir).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(dumps.len(), 1);
+        let bundle: serde_json::Value =
+            serde_json::from_slice(&fs::read(dumps[0].path()).unwrap()).unwrap();
+        assert_eq!(bundle["block_number"], 42);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
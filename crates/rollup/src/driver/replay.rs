@@ -0,0 +1,937 @@
+//! Scripted L1 replay harness for driver tests.
+//!
+//! A [Fixture] records a set of L1 blocks (with their transactions, receipts and blob
+//! sidecars), the L2 blocks derived from them, and a sequence of [ReplayStep]s describing
+//! how the canonical L1 chain evolves. The [Replay] harness turns the steps into
+//! [ChainNotification]s delivered by a [MockDriverContext], and runs them through the
+//! [Driver] and the kona derivation pipeline end-to-end, with no network access.
+
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use alloy::{
+    consensus::{
+        Header, Receipt, SignableTransaction, TxEip1559, TxEip4844, TxEip4844Variant, TxEnvelope,
+    },
+    eips::{
+        eip2718::{Decodable2718, Encodable2718},
+        eip4844::{Blob, Bytes48},
+        BlockNumHash,
+    },
+    primitives::{address, keccak256, Address, Bytes, Log, Signature, TxKind, B256, U256},
+    rpc::types::Block,
+};
+use alloy_rlp::Encodable;
+use async_trait::async_trait;
+use clap::Parser;
+use eyre::{eyre, Result};
+use hashbrown::HashMap;
+use k256::{ecdsa::SigningKey, elliptic_curve::sec1::ToEncodedPoint};
+use kona_derive::traits::{ChainProvider, L2ChainProvider, OriginProvider};
+use kona_primitives::L2ExecutionPayloadEnvelope;
+use kona_providers::LayeredBlobProvider;
+use op_alloy_genesis::{ChainGenesis, RollupConfig, SystemConfig};
+use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use op_alloy_rpc_types_engine::OptimismAttributesWithParent;
+use reth::{primitives::BlobTransactionSidecar, rpc::types::BlockTransactions};
+use serde::Deserialize;
+use tokio::sync::broadcast;
+
+use super::{
+    context::{Blocks, ChainNotification, DriverContext, MockDriverContext},
+    Driver, DriverEvent,
+};
+use crate::{AttributesValidator, HeraArgsExt, RollupPipeline};
+
+/// The L2 block time of the replayed chain, matching the L1 block time of the fixtures.
+const BLOCK_TIME: u64 = 12;
+
+/// The private key of the batcher in the genesis system config of the replayed chain.
+const BATCHER_KEY: [u8; 32] = [0x11; 32];
+
+/// The batch inbox address of the replayed chain.
+const BATCH_INBOX: Address = address!("ff00000000000000000000000000000000000010");
+
+/// The address of the `SystemConfig` contract of the replayed chain.
+const SYSTEM_CONFIG: Address = address!("0000000000000000000000000000000000005c57");
+
+/// The user transaction included in every batch. Only its type byte matters to
+/// the derivation pipeline and to the replay validator.
+const USER_TX: [u8; 2] = [0x02, 0xc0];
+
+/// The number of consecutive driver steps without progress after which the
+/// pipeline is considered to be waiting for more L1 data.
+const MAX_IDLE_STEPS: usize = 16;
+
+/// A recorded L1 block.
+#[derive(Debug, Clone, Deserialize)]
+pub struct L1BlockFixture {
+    /// The block number.
+    pub number: u64,
+    /// The block hash.
+    pub hash: B256,
+    /// The parent block hash.
+    pub parent_hash: B256,
+    /// The block timestamp.
+    pub timestamp: u64,
+    /// The EIP-2718 encoded transactions of the block.
+    #[serde(default)]
+    pub transactions: Vec<Bytes>,
+    /// The receipts of the block.
+    #[serde(default)]
+    pub receipts: Vec<Receipt>,
+    /// The blob sidecars of the block.
+    #[serde(default)]
+    pub blob_sidecars: Vec<BlobTransactionSidecar>,
+}
+
+impl L1BlockFixture {
+    /// Returns the [BlockInfo] of the block.
+    const fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            hash: self.hash,
+            number: self.number,
+            parent_hash: self.parent_hash,
+            timestamp: self.timestamp,
+        }
+    }
+
+    /// Returns the block as delivered in a [ChainNotification].
+    fn rpc_block(&self) -> Block<TxEnvelope> {
+        Block {
+            header: alloy::rpc::types::Header {
+                hash: self.hash,
+                parent_hash: self.parent_hash,
+                number: self.number,
+                timestamp: self.timestamp,
+                ..Default::default()
+            },
+            uncles: Vec::new(),
+            transactions: BlockTransactions::Full(Vec::new()),
+            size: None,
+            withdrawals: None,
+        }
+    }
+}
+
+/// An L2 block derived from a recorded L1 origin block.
+///
+/// The same L2 block number can appear several times with different L1 origins:
+/// the canonical one is the block whose L1 origin is part of the canonical L1 chain.
+#[derive(Debug, Clone, Deserialize)]
+pub struct L2BlockFixture {
+    /// The block number.
+    pub number: u64,
+    /// The block hash.
+    pub hash: B256,
+    /// The hash of the L1 origin block.
+    pub l1_origin: B256,
+    /// The sequence number of the block in its epoch.
+    #[serde(default)]
+    pub seq_num: u64,
+}
+
+/// A scripted change of the canonical L1 chain.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayStep {
+    /// The given L1 blocks are appended to the canonical chain.
+    New(Vec<B256>),
+    /// The given L1 blocks are removed from the canonical chain.
+    Revert(Vec<B256>),
+    /// The old L1 blocks are replaced by the new ones in the canonical chain.
+    Reorg {
+        /// The L1 blocks removed from the canonical chain.
+        old: Vec<B256>,
+        /// The L1 blocks added to the canonical chain.
+        new: Vec<B256>,
+    },
+    /// The given L1 block number is finalized.
+    Finalized(u64),
+}
+
+/// How the batcher submits batches to L1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchSubmission {
+    /// In the calldata of the batcher transactions.
+    Calldata,
+    /// In the blobs of the batcher transactions.
+    Blobs,
+}
+
+/// A recorded L1 chain and the L2 chain derived from it.
+///
+/// The L1 origin of the first L2 block is the anchor of the replay: it is part of the
+/// canonical chain from the start, and the driver starts deriving from that L2 block.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fixture {
+    /// All the L1 blocks referenced by the fixture, including reorged ones.
+    pub l1_blocks: Vec<L1BlockFixture>,
+    /// All the L2 blocks derived from the L1 blocks, ordered by number.
+    pub l2_blocks: Vec<L2BlockFixture>,
+    /// The changes of the canonical L1 chain, in order.
+    pub steps: Vec<ReplayStep>,
+    /// The L2 block numbers that fail validation.
+    #[serde(default)]
+    pub invalid_l2_blocks: Vec<u64>,
+    /// The activation time of Ecotone, from which batches are read from blobs.
+    #[serde(default)]
+    pub ecotone_time: Option<u64>,
+}
+
+impl Fixture {
+    /// Parses a fixture from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Returns the rollup config of the replayed chain, whose genesis batcher
+    /// is the owner of [BATCHER_KEY].
+    fn rollup_config(&self) -> RollupConfig {
+        let system_config =
+            SystemConfig { batcher_address: batcher_address(&batcher_key()), ..Default::default() };
+        RollupConfig {
+            genesis: ChainGenesis { system_config: Some(system_config), ..Default::default() },
+            block_time: BLOCK_TIME,
+            max_sequencer_drift: 600,
+            seq_window_size: 3600,
+            channel_timeout: 1,
+            batch_inbox_address: BATCH_INBOX,
+            l1_system_config_address: SYSTEM_CONFIG,
+            ecotone_time: self.ecotone_time,
+            ..Default::default()
+        }
+    }
+
+    /// Submits a batch for every L2 block with a parent in the fixture, in its L1
+    /// origin block, signed with the key the given function returns for that L1 block.
+    pub fn submit_batches(
+        &mut self,
+        submission: BatchSubmission,
+        batcher: impl Fn(u64) -> SigningKey,
+    ) {
+        for (l1_origin, data) in self.batches() {
+            let block = self.l1_blocks.iter_mut().find(|b| b.hash == l1_origin).unwrap();
+            let key = batcher(block.number);
+            let tx = match submission {
+                BatchSubmission::Calldata => calldata_tx(&key, data),
+                BatchSubmission::Blobs => {
+                    let sidecar = blob_sidecar(&data);
+                    let tx = blob_tx(&key, sidecar.versioned_hashes().collect());
+                    block.blob_sidecars.push(sidecar);
+                    tx
+                }
+            };
+            block.transactions.push(tx);
+        }
+    }
+
+    /// Returns the batcher data of every L2 block with a parent in the fixture, with the
+    /// hash of its L1 origin block.
+    ///
+    /// Each L2 block is batched in its own channel, on top of the L2 block at the previous
+    /// height whose L1 origin is on the same L1 branch.
+    fn batches(&self) -> Vec<(B256, Vec<u8>)> {
+        self.l2_blocks
+            .iter()
+            .filter_map(|l2| {
+                let parent = self.l2_blocks.iter().find(|parent| {
+                    parent.number + 1 == l2.number &&
+                        self.is_l1_ancestor(parent.l1_origin, l2.l1_origin)
+                })?;
+                let epoch = self.l1_blocks.iter().find(|b| b.hash == l2.l1_origin)?;
+                let timestamp = epoch.timestamp + l2.seq_num * BLOCK_TIME;
+                let batch = single_batch(parent.hash, epoch, timestamp);
+                let channel_id = &keccak256(l2.hash)[..16];
+                Some((l2.l1_origin, frame(channel_id, &zlib_stored(&batch))))
+            })
+            .collect()
+    }
+
+    /// Returns true if the L1 block `ancestor` is the given L1 block or one of its ancestors.
+    fn is_l1_ancestor(&self, ancestor: B256, mut hash: B256) -> bool {
+        loop {
+            if hash == ancestor {
+                return true;
+            }
+            match self.l1_blocks.iter().find(|b| b.hash == hash) {
+                Some(block) => hash = block.parent_hash,
+                None => return false,
+            }
+        }
+    }
+}
+
+/// Returns the signing key of the genesis batcher of the replayed chain.
+pub fn batcher_key() -> SigningKey {
+    SigningKey::from_slice(&BATCHER_KEY).unwrap()
+}
+
+/// Returns the address of the given batcher key.
+pub fn batcher_address(key: &SigningKey) -> Address {
+    Address::from_raw_public_key(&key.verifying_key().to_encoded_point(false).as_bytes()[1..])
+}
+
+/// Encodes a single batch with [USER_TX] as an RLP string, as read from a channel.
+fn single_batch(parent_hash: B256, epoch: &L1BlockFixture, timestamp: u64) -> Vec<u8> {
+    let mut payload = Vec::new();
+    parent_hash.encode(&mut payload);
+    epoch.number.encode(&mut payload);
+    epoch.hash.encode(&mut payload);
+    timestamp.encode(&mut payload);
+    vec![Bytes::from_static(&USER_TX)].encode(&mut payload);
+
+    // The single batch type, followed by the batch RLP list.
+    let mut batch = vec![0x00];
+    alloy_rlp::Header { list: true, payload_length: payload.len() }.encode(&mut batch);
+    batch.extend(payload);
+
+    let mut encoded = Vec::new();
+    batch.as_slice().encode(&mut encoded);
+    encoded
+}
+
+/// Compresses the given data into a zlib stream made of a single stored block.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let len = data.len() as u16;
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    let mut stream = vec![0x78, 0x01, 0x01];
+    stream.extend(len.to_le_bytes());
+    stream.extend((!len).to_le_bytes());
+    stream.extend(data);
+    stream.extend(((b << 16) | a).to_be_bytes());
+    stream
+}
+
+/// Encodes the given channel data as the single frame of the channel, prefixed
+/// with the derivation version.
+fn frame(channel_id: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x00];
+    frame.extend(channel_id);
+    frame.extend(0u16.to_be_bytes());
+    frame.extend((data.len() as u32).to_be_bytes());
+    frame.extend(data);
+    frame.push(1);
+    frame
+}
+
+/// Encodes the given data into a blob, with version 0 of the OP Stack blob encoding.
+fn encode_blob(data: &[u8]) -> Blob {
+    let mut blob = Blob::ZERO;
+    let mut input = data.iter().copied().chain(std::iter::repeat(0));
+    let mut read = |out: &mut [u8]| out.iter_mut().for_each(|byte| *byte = input.next().unwrap());
+
+    // Each round encodes 127 bytes of data into 4 field elements, spreading the 4 bytes
+    // that do not fit in the 31-byte tails over the 6 low bits of their first byte.
+    for round in 0..1024 {
+        let fe = &mut blob[round * 128..(round + 1) * 128];
+        if round == 0 {
+            fe[1] = 0;
+            fe[2..5].copy_from_slice(&(data.len() as u32).to_be_bytes()[1..]);
+            read(&mut fe[5..32]);
+        } else {
+            read(&mut fe[1..32]);
+        }
+
+        let mut x = [0u8; 1];
+        read(&mut x);
+        read(&mut fe[33..64]);
+        let mut y = [0u8; 1];
+        read(&mut y);
+        read(&mut fe[65..96]);
+        let mut z = [0u8; 1];
+        read(&mut z);
+        read(&mut fe[97..128]);
+
+        let (x, y, z) = (x[0], y[0], z[0]);
+        fe[0] = x & 0b0011_1111;
+        fe[32] = (y & 0b0000_1111) | ((x & 0b1100_0000) >> 2);
+        fe[64] = z & 0b0011_1111;
+        fe[96] = ((z & 0b1100_0000) >> 2) | ((y & 0b1111_0000) >> 4);
+    }
+
+    blob
+}
+
+/// Returns the sidecar of a blob holding the given data.
+///
+/// The commitment is not a KZG commitment of the blob: it only needs to be unique,
+/// as blobs are looked up by the versioned hash derived from it.
+fn blob_sidecar(data: &[u8]) -> BlobTransactionSidecar {
+    BlobTransactionSidecar {
+        blobs: vec![encode_blob(data)],
+        commitments: vec![Bytes48::right_padding_from(keccak256(data).as_slice())],
+        proofs: vec![Bytes48::ZERO],
+    }
+}
+
+/// Signs the given transaction with the given key.
+fn sign<T: SignableTransaction<Signature>>(key: &SigningKey, tx: &T) -> Signature {
+    let (signature, recovery_id) =
+        key.sign_prehash_recoverable(tx.signature_hash().as_slice()).unwrap();
+    let mut bytes = signature.to_bytes().to_vec();
+    bytes.push(recovery_id.to_byte());
+    Signature::try_from(bytes.as_slice()).unwrap()
+}
+
+/// Returns an encoded batcher transaction sending the given data to the batch inbox.
+fn calldata_tx(key: &SigningKey, data: Vec<u8>) -> Bytes {
+    let tx = TxEip1559 {
+        chain_id: 1,
+        to: TxKind::Call(BATCH_INBOX),
+        input: data.into(),
+        ..Default::default()
+    };
+    let signature = sign(key, &tx);
+    TxEnvelope::Eip1559(tx.into_signed(signature)).encoded_2718().into()
+}
+
+/// Returns an encoded batcher transaction sending the blobs with the given versioned
+/// hashes to the batch inbox.
+fn blob_tx(key: &SigningKey, blob_versioned_hashes: Vec<B256>) -> Bytes {
+    let tx = TxEip4844Variant::TxEip4844(TxEip4844 {
+        chain_id: 1,
+        to: BATCH_INBOX,
+        blob_versioned_hashes,
+        ..Default::default()
+    });
+    let signature = sign(key, &tx);
+    TxEnvelope::Eip4844(tx.into_signed(signature)).encoded_2718().into()
+}
+
+/// Returns a receipt of the `SystemConfig` contract updating the batcher address.
+fn batcher_update_receipt(batcher: Address) -> Receipt {
+    let topics = vec![keccak256("ConfigUpdate(uint256,uint8,bytes)"), B256::ZERO, B256::ZERO];
+    let mut data = Vec::with_capacity(96);
+    data.extend(U256::from(32).to_be_bytes::<32>());
+    data.extend(U256::from(32).to_be_bytes::<32>());
+    data.extend(batcher.into_word());
+    let log = Log::new_unchecked(SYSTEM_CONFIG, topics, data.into());
+    Receipt { status: true.into(), cumulative_gas_used: 0, logs: vec![log] }
+}
+
+/// The L1 chain state shared by the replay providers.
+#[derive(Debug, Clone, Default)]
+struct ReplayChain(Arc<Mutex<ReplayChainInner>>);
+
+#[derive(Debug, Default)]
+struct ReplayChainInner {
+    /// All the recorded L1 blocks, by hash.
+    blocks: HashMap<B256, L1BlockFixture>,
+    /// The canonical L1 chain, by number.
+    canonical: BTreeMap<u64, B256>,
+    /// All the recorded L2 blocks.
+    l2_blocks: Vec<L2BlockFixture>,
+}
+
+impl ReplayChain {
+    fn new(fixture: &Fixture) -> Self {
+        let blocks = fixture.l1_blocks.iter().map(|b| (b.hash, b.clone())).collect();
+        let inner = ReplayChainInner {
+            blocks,
+            canonical: BTreeMap::new(),
+            l2_blocks: fixture.l2_blocks.clone(),
+        };
+        Self(Arc::new(Mutex::new(inner)))
+    }
+
+    fn block(&self, hash: B256) -> Option<L1BlockFixture> {
+        self.0.lock().unwrap().blocks.get(&hash).cloned()
+    }
+
+    fn canonical_block(&self, number: u64) -> Option<L1BlockFixture> {
+        let inner = self.0.lock().unwrap();
+        inner.canonical.get(&number).and_then(|hash| inner.blocks.get(hash)).cloned()
+    }
+
+    fn blocks(&self, hashes: &[B256]) -> Vec<L1BlockFixture> {
+        hashes.iter().map(|hash| self.block(*hash).expect("unknown L1 block in fixture")).collect()
+    }
+
+    /// Appends the given L1 blocks to the canonical chain.
+    fn commit(&self, hashes: &[B256]) {
+        for block in self.blocks(hashes) {
+            self.0.lock().unwrap().canonical.insert(block.number, block.hash);
+        }
+    }
+
+    /// Removes the given L1 blocks from the canonical chain.
+    fn revert(&self, hashes: &[B256]) {
+        for block in self.blocks(hashes) {
+            self.0.lock().unwrap().canonical.remove(&block.number);
+        }
+    }
+
+    /// Applies the given step to the canonical chain, and returns the corresponding
+    /// notification delivered to the driver.
+    fn apply(&self, step: &ReplayStep) -> ChainNotification {
+        let rpc_blocks = |hashes: &[B256]| -> Blocks {
+            self.blocks(hashes).iter().map(L1BlockFixture::rpc_block).collect::<Vec<_>>().into()
+        };
+
+        match step {
+            ReplayStep::New(new) => {
+                self.commit(new);
+                ChainNotification::New { new_blocks: rpc_blocks(new) }
+            }
+            ReplayStep::Revert(old) => {
+                self.revert(old);
+                ChainNotification::Revert { old_blocks: rpc_blocks(old) }
+            }
+            ReplayStep::Reorg { old, new } => {
+                self.revert(old);
+                self.commit(new);
+                ChainNotification::Reorg {
+                    old_blocks: rpc_blocks(old),
+                    new_blocks: rpc_blocks(new),
+                }
+            }
+            ReplayStep::Finalized(number) => ChainNotification::Finalized { l1_block: *number },
+        }
+    }
+
+    /// Returns the canonical L2 block at the given number: the one whose L1 origin
+    /// is part of the canonical L1 chain.
+    fn l2_block(&self, number: u64) -> Option<L2BlockInfo> {
+        let inner = self.0.lock().unwrap();
+        let canonical = |l2: &&L2BlockFixture| {
+            let origin = &inner.blocks[&l2.l1_origin];
+            inner.canonical.get(&origin.number) == Some(&origin.hash)
+        };
+
+        let block = inner.l2_blocks.iter().filter(|l2| l2.number == number).find(canonical)?;
+        let parent_hash = inner
+            .l2_blocks
+            .iter()
+            .filter(|l2| l2.number + 1 == number)
+            .find(canonical)
+            .map(|parent| parent.hash)
+            .unwrap_or_default();
+        let origin = &inner.blocks[&block.l1_origin];
+
+        Some(L2BlockInfo {
+            block_info: BlockInfo {
+                hash: block.hash,
+                number,
+                parent_hash,
+                timestamp: origin.timestamp + block.seq_num * BLOCK_TIME,
+            },
+            l1_origin: BlockNumHash { number: origin.number, hash: origin.hash },
+            seq_num: block.seq_num,
+        })
+    }
+}
+
+/// An L1 [ChainProvider] serving the canonical blocks of a [ReplayChain].
+#[derive(Debug, Clone)]
+struct ReplayChainProvider(ReplayChain);
+
+#[async_trait]
+impl ChainProvider for ReplayChainProvider {
+    type Error = eyre::Error;
+
+    async fn header_by_hash(&mut self, hash: B256) -> Result<Header> {
+        let block = self.0.block(hash).ok_or(eyre!("Header not found"))?;
+        Ok(Header {
+            parent_hash: block.parent_hash,
+            number: block.number,
+            timestamp: block.timestamp,
+            ..Default::default()
+        })
+    }
+
+    async fn block_info_by_number(&mut self, number: u64) -> Result<BlockInfo> {
+        let block = self.0.canonical_block(number).ok_or(eyre!("Block not found"))?;
+        Ok(block.block_info())
+    }
+
+    async fn receipts_by_hash(&mut self, hash: B256) -> Result<Vec<Receipt>> {
+        Ok(self.0.block(hash).ok_or(eyre!("Receipts not found"))?.receipts)
+    }
+
+    async fn block_info_and_transactions_by_hash(
+        &mut self,
+        hash: B256,
+    ) -> Result<(BlockInfo, Vec<TxEnvelope>)> {
+        let block = self.0.block(hash).ok_or(eyre!("Block not found"))?;
+        let transactions = block
+            .transactions
+            .iter()
+            .map(|tx| TxEnvelope::decode_2718(&mut tx.as_ref()))
+            .collect::<Result<_, _>>()
+            .map_err(|e| eyre!("Invalid transaction in fixture: {:?}", e))?;
+        Ok((block.block_info(), transactions))
+    }
+}
+
+/// An [L2ChainProvider] serving the canonical L2 blocks of a [ReplayChain].
+///
+/// System configs are not recorded in fixtures: the genesis one is returned for every block.
+#[derive(Debug, Clone)]
+struct ReplayL2ChainProvider(ReplayChain);
+
+#[async_trait]
+impl L2ChainProvider for ReplayL2ChainProvider {
+    type Error = eyre::Error;
+
+    async fn l2_block_info_by_number(&mut self, number: u64) -> Result<L2BlockInfo> {
+        self.0.l2_block(number).ok_or(eyre!("L2 block {} not found", number))
+    }
+
+    async fn payload_by_number(&mut self, _: u64) -> Result<L2ExecutionPayloadEnvelope> {
+        Err(eyre!("Payloads are not recorded in replay fixtures"))
+    }
+
+    async fn system_config_by_number(
+        &mut self,
+        _: u64,
+        rollup_config: Arc<RollupConfig>,
+    ) -> Result<SystemConfig> {
+        rollup_config.genesis.system_config.ok_or(eyre!("Missing genesis system config"))
+    }
+}
+
+/// A validator that rejects a fixed set of L2 block numbers, unless their
+/// attributes only contain deposits.
+#[derive(Debug)]
+struct ReplayValidator(HashSet<u64>);
+
+#[async_trait]
+impl AttributesValidator for ReplayValidator {
+    async fn validate(&self, attributes: &OptimismAttributesWithParent) -> Result<bool> {
+        let mut transactions = attributes.attributes.transactions.iter().flatten();
+        let deposits_only = transactions.all(|tx| tx.first() == Some(&0x7E));
+        Ok(deposits_only || !self.0.contains(&(attributes.parent.block_info.number + 1)))
+    }
+}
+
+/// The CLI wrapper used to build the driver arguments.
+#[derive(Debug, Parser)]
+struct ReplayCli {
+    #[clap(flatten)]
+    args: HeraArgsExt,
+}
+
+/// The driver type used by the [Replay] harness.
+type ReplayDriver =
+    Driver<MockDriverContext, ReplayChainProvider, LayeredBlobProvider, ReplayL2ChainProvider>;
+
+/// The derivation pipeline type used by the [Replay] harness.
+type ReplayPipeline =
+    RollupPipeline<ReplayChainProvider, LayeredBlobProvider, ReplayL2ChainProvider>;
+
+/// A harness replaying a [Fixture] into a [Driver].
+#[derive(Debug)]
+pub struct Replay {
+    /// The driver under test.
+    driver: ReplayDriver,
+    /// The derivation pipeline, reading from the replayed L1 chain.
+    pipeline: ReplayPipeline,
+    /// The replayed L1 chain.
+    chain: ReplayChain,
+    /// The steps left to replay, matching the notifications of the driver context.
+    steps: VecDeque<ReplayStep>,
+    /// The events emitted by the driver.
+    events: broadcast::Receiver<DriverEvent>,
+}
+
+impl Replay {
+    /// Creates a new harness for the given fixture, with the given extra CLI arguments
+    /// (e.g. `["--hera.on-invalid-payload", "skip"]`).
+    pub async fn new(fixture: Fixture, args: &[&str]) -> Result<Self> {
+        let start = fixture.l2_blocks.first().ok_or(eyre!("Empty fixture"))?.clone();
+        let start_block = start.number.to_string();
+        let cli_args = ["hera", "--hera.start-block", start_block.as_str()];
+        let cli = ReplayCli::try_parse_from(cli_args.iter().chain(args))?;
+
+        let chain = ReplayChain::new(&fixture);
+        let cfg = Arc::new(fixture.rollup_config());
+        let mut blob_provider = LayeredBlobProvider::new("http://127.0.0.1:1".parse()?, None);
+        for block in &fixture.l1_blocks {
+            blob_provider.insert_blob_sidecars(block.hash, block.blob_sidecars.clone());
+        }
+
+        let ctx = MockDriverContext::default();
+        let driver = Driver::with_components(
+            ctx,
+            cli.args,
+            cfg,
+            ReplayChainProvider(chain.clone()),
+            blob_provider,
+        )?;
+        let mut driver = driver.with_l2_chain_provider(ReplayL2ChainProvider(chain.clone()));
+        driver.validator =
+            Box::new(ReplayValidator(fixture.invalid_l2_blocks.into_iter().collect()));
+        let events = driver.subscribe();
+
+        // The L1 origin of the first L2 block anchors the replay.
+        chain.commit(&[start.l1_origin]);
+        let pipeline = driver.init_pipeline().await?;
+        let steps = fixture.steps.into_iter().collect::<VecDeque<_>>();
+
+        Ok(Self { driver, pipeline, chain, steps, events })
+    }
+
+    /// Replays all the steps of the fixture, deriving as many L2 blocks as possible
+    /// after each one.
+    pub async fn run(&mut self) -> Result<()> {
+        loop {
+            self.derive().await?;
+
+            let Some(step) = self.steps.pop_front() else {
+                return Ok(());
+            };
+            // Notifications are built as the steps are applied, so that the
+            // providers observe the new canonical chain when they are delivered.
+            self.driver.ctx.push(self.chain.apply(&step));
+
+            let notification =
+                self.driver.ctx.recv_notification().await.expect("Notification must be scheduled");
+            self.driver.handle_notification(notification, &mut self.pipeline).await?;
+        }
+    }
+
+    /// Steps the driver until the pipeline needs more L1 data: deriving a block
+    /// takes several steps, as the pipeline reads through the stages one at a time.
+    async fn derive(&mut self) -> Result<()> {
+        let mut idle_steps = 0;
+        while idle_steps < MAX_IDLE_STEPS {
+            let origin = self.pipeline.origin();
+            if self.driver.step(&mut self.pipeline).await? || self.pipeline.origin() != origin {
+                idle_steps = 0;
+            } else {
+                idle_steps += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the events emitted by the driver since the last call.
+    pub fn events(&mut self) -> Vec<DriverEvent> {
+        std::iter::from_fn(|| self.events.try_recv().ok()).collect()
+    }
+}
+
+impl<DC, CP, BP, L2CP> Driver<DC, CP, BP, L2CP> {
+    /// Replaces the L2 chain provider of the driver.
+    fn with_l2_chain_provider<L2>(self, l2_chain_provider: L2) -> Driver<DC, CP, BP, L2> {
+        Driver {
+            cfg: self.cfg,
+            ctx: self.ctx,
+            l1_chain_provider: self.l1_chain_provider,
+            blob_provider: self.blob_provider,
+            l2_chain_provider,
+            l2_provider: self.l2_provider,
+            cursor: self.cursor,
+            validator: self.validator,
+            events: self.events,
+            sync_status: self.sync_status,
+            output_verifier: self.output_verifier,
+            rpc: self.rpc,
+            invalid_payload_handler: self.invalid_payload_handler,
+            retry: self.retry,
+            checkpoint: self.checkpoint,
+            start_block: self.start_block,
+            last_processed_l1: self.last_processed_l1,
+            unsafe_block_rx: self.unsafe_block_rx,
+            unsafe_blocks: self.unsafe_blocks,
+        }
+    }
+}
+
+const L1_REORG: &str = include_str!("../../testdata/replay/l1_reorg.json");
+
+fn hash(tag: u8) -> B256 {
+    B256::with_last_byte(tag)
+}
+
+fn safe_heads(events: &[DriverEvent]) -> Vec<(u64, B256)> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            DriverEvent::SafeHead(head) => Some((head.block_info.number, head.block_info.hash)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A linear chain: L2 blocks 10..=14, with L1 origins 0..=4, without batches.
+fn linear_chain() -> Fixture {
+    let l1_blocks = (0..=4)
+        .map(|n| L1BlockFixture {
+            number: n,
+            hash: hash(0xa0 + n as u8),
+            parent_hash: if n == 0 { B256::ZERO } else { hash(0xa0 + n as u8 - 1) },
+            timestamp: 1000 + BLOCK_TIME * n,
+            transactions: Vec::new(),
+            receipts: Vec::new(),
+            blob_sidecars: Vec::new(),
+        })
+        .collect();
+    let l2_blocks = (0..=4)
+        .map(|n| L2BlockFixture {
+            number: 10 + n,
+            hash: hash(0x10 + n as u8),
+            l1_origin: hash(0xa0 + n as u8),
+            seq_num: 0,
+        })
+        .collect();
+    let steps = vec![
+        ReplayStep::New(vec![hash(0xa1), hash(0xa2)]),
+        ReplayStep::New(vec![hash(0xa3), hash(0xa4)]),
+    ];
+    Fixture { l1_blocks, l2_blocks, steps, invalid_l2_blocks: Vec::new(), ecotone_time: None }
+}
+
+/// The linear chain, with its batches submitted as calldata by the genesis batcher.
+fn linear_fixture() -> Fixture {
+    let mut fixture = linear_chain();
+    fixture.submit_batches(BatchSubmission::Calldata, |_| batcher_key());
+    fixture
+}
+
+#[tokio::test]
+async fn test_replay_derives_l2_chain() {
+    let mut replay = Replay::new(linear_fixture(), &[]).await.unwrap();
+    replay.run().await.unwrap();
+
+    let heads = replay.driver.cursor.heads();
+    assert_eq!(heads.safe_head.block_info.number, 14);
+    assert_eq!(heads.unsafe_head, heads.safe_head);
+    // The processed tips follow the safe head's L1 origin minus the channel timeout.
+    assert_eq!(replay.driver.ctx.processed_tips(), &[1, 2, 3]);
+    assert_eq!(
+        safe_heads(&replay.events()).iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+        vec![11, 12, 13, 14]
+    );
+}
+
+#[tokio::test]
+async fn test_replay_derives_l2_chain_from_blobs() {
+    let mut fixture = linear_chain();
+    fixture.ecotone_time = Some(0);
+    fixture.submit_batches(BatchSubmission::Blobs, |_| batcher_key());
+    let mut replay = Replay::new(fixture, &[]).await.unwrap();
+    replay.run().await.unwrap();
+
+    assert_eq!(replay.driver.cursor.heads().safe_head.block_info.number, 14);
+}
+
+#[tokio::test]
+async fn test_replay_follows_batcher_rotation() {
+    let rotated = SigningKey::from_slice(&[0x22; 32]).unwrap();
+    let batcher = |number: u64| if number < 2 { batcher_key() } else { rotated.clone() };
+
+    // Without the system config update, the batches of the new batcher are ignored.
+    let mut fixture = linear_chain();
+    fixture.submit_batches(BatchSubmission::Calldata, batcher);
+    let mut replay = Replay::new(fixture.clone(), &[]).await.unwrap();
+    replay.run().await.unwrap();
+    assert_eq!(replay.driver.cursor.heads().safe_head.block_info.number, 11);
+
+    // The batcher is rotated by a receipt of L1 block 2.
+    fixture.l1_blocks[2].receipts.push(batcher_update_receipt(batcher_address(&rotated)));
+    let mut replay = Replay::new(fixture, &[]).await.unwrap();
+    replay.run().await.unwrap();
+    assert_eq!(replay.driver.cursor.heads().safe_head.block_info.number, 14);
+}
+
+#[tokio::test]
+async fn test_replay_l1_reorg_resets_cursor() {
+    let mut fixture = Fixture::from_json(L1_REORG).unwrap();
+    fixture.submit_batches(BatchSubmission::Calldata, |_| batcher_key());
+    let mut replay = Replay::new(fixture, &[]).await.unwrap();
+    replay.run().await.unwrap();
+
+    // The reorg at L1 block 3 resets the cursor to the L2 block derived from
+    // L1 block 1 (the fork block minus the channel timeout). Blocks 12 and 13 are
+    // derived again, the latter from the new L1 chain.
+    assert_eq!(
+        safe_heads(&replay.events()),
+        vec![
+            (11, hash(0x11)),
+            (12, hash(0x12)),
+            (13, hash(0x13)),
+            (11, hash(0x11)),
+            (12, hash(0x12)),
+            (13, hash(0xd3)),
+            (14, hash(0xd4)),
+        ]
+    );
+
+    let heads = replay.driver.cursor.heads();
+    assert_eq!(heads.safe_head.block_info.hash, hash(0xd4));
+    assert_eq!(heads.finalized_head.block_info.number, 12);
+    // The processed tip does not move backwards when the pipeline is reset.
+    assert_eq!(replay.driver.ctx.processed_tips(), &[1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_replay_l1_revert() {
+    let mut fixture = linear_fixture();
+    fixture.steps.insert(1, ReplayStep::Revert(vec![hash(0xa2)]));
+    fixture.steps[2] = ReplayStep::New(vec![hash(0xa2), hash(0xa3)]);
+    let mut replay = Replay::new(fixture, &[]).await.unwrap();
+    replay.run().await.unwrap();
+
+    // Reverting L1 block 2 rewinds the cursor to L1 block 0, then derivation resumes.
+    assert_eq!(
+        safe_heads(&replay.events()).iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+        vec![11, 12, 10, 11, 12, 13]
+    );
+    assert_eq!(replay.driver.cursor.heads().safe_head.block_info.number, 13);
+}
+
+#[tokio::test]
+async fn test_replay_invalid_payload_skip() {
+    let mut fixture = linear_fixture();
+    fixture.invalid_l2_blocks = vec![12];
+    let mut replay = Replay::new(fixture, &["--hera.on-invalid-payload", "skip"]).await.unwrap();
+    replay.run().await.unwrap();
+
+    // The invalid block is replaced by a deposit-only block, and the blocks
+    // after it are derived on top of it.
+    assert_eq!(replay.driver.cursor.heads().safe_head.block_info.number, 14);
+    assert_eq!(
+        safe_heads(&replay.events()).iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+        vec![11, 12, 13, 14]
+    );
+}
+
+#[tokio::test]
+async fn test_replay_invalid_payload_retry() {
+    let mut fixture = linear_fixture();
+    fixture.invalid_l2_blocks = vec![12];
+    let mut replay = Replay::new(fixture, &[]).await.unwrap();
+    replay.run().await.unwrap();
+
+    // The invalid attributes are validated again, without ever advancing past them.
+    assert_eq!(replay.driver.cursor.heads().safe_head.block_info.number, 11);
+}
+
+#[tokio::test]
+async fn test_replay_invalid_payload_halt() {
+    let mut fixture = linear_fixture();
+    fixture.invalid_l2_blocks = vec![12];
+    let mut replay = Replay::new(fixture, &["--hera.on-invalid-payload", "halt"]).await.unwrap();
+
+    assert!(replay.run().await.is_err());
+    assert_eq!(replay.driver.cursor.heads().safe_head.block_info.number, 11);
+}
+
+#[tokio::test]
+async fn test_mock_context_replays_notifications() {
+    let mut ctx = MockDriverContext::new([ChainNotification::Finalized { l1_block: 1 }]);
+    assert_eq!(ctx.recv_notification().await.unwrap().finalized_block(), Some(1));
+    assert!(ctx.recv_notification().await.is_none());
+
+    ctx.send_processed_tip_event(7).unwrap();
+    assert_eq!(ctx.processed_tips(), &[7]);
+}
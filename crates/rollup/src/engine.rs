@@ -0,0 +1,648 @@
+//! Engine API client for driving an L2 execution client.
+
+use std::{fmt::Debug, sync::Arc};
+
+use alloy::{
+    primitives::{B256, U256, U64},
+    rpc::types::engine::{ForkchoiceState, ForkchoiceUpdated, PayloadId, PayloadStatus},
+};
+use async_trait::async_trait;
+use eyre::{bail, eyre, Result, WrapErr};
+use op_alloy_genesis::RollupConfig;
+use op_alloy_protocol::L2BlockInfo;
+use op_alloy_rpc_types_engine::{OptimismAttributesWithParent, OptimismPayloadAttributes};
+use op_net::types::envelope::ExecutionPayloadEnvelope;
+use reqwest::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    Client,
+};
+use reth::rpc::types::engine::{Claims, JwtSecret};
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use tracing::{debug, error, trace, warn};
+use url::Url;
+
+use crate::{AttributesValidator, TrustedValidator, UnsafePayloadStatus};
+
+/// A minimal authenticated client for the L2 Engine API.
+///
+/// The client picks the method version to call based on the hardforks
+/// active at the timestamp of the payload:
+/// - Ecotone: `V3` (Cancun)
+/// - Canyon: `V2` (Shanghai)
+/// - Bedrock: `V1`
+#[derive(Debug, Clone)]
+pub struct EngineClient {
+    /// The engine API URL.
+    url: Url,
+    /// The reqwest client.
+    client: Client,
+    /// The JWT secret token for the engine API.
+    jwt_secret: JwtSecret,
+    /// The rollup configuration, used to select the method versions.
+    cfg: Arc<RollupConfig>,
+}
+
+impl EngineClient {
+    /// Creates a new [`EngineClient`] from the provided [Url] and [JwtSecret].
+    pub fn new_http(url: Url, jwt: JwtSecret, cfg: Arc<RollupConfig>) -> Self {
+        Self { url, client: Client::new(), jwt_secret: jwt, cfg }
+    }
+
+    /// Calls `engine_forkchoiceUpdatedV{1,2,3}` with the given [ForkchoiceState]
+    /// and optional payload attributes to start building a new payload.
+    pub async fn forkchoice_updated(
+        &self,
+        state: ForkchoiceState,
+        attributes: Option<&OptimismPayloadAttributes>,
+        timestamp: u64,
+    ) -> Result<ForkchoiceUpdated> {
+        let method = format!("engine_forkchoiceUpdatedV{}", self.version_at(timestamp));
+        let result = self.call(&method, json!([state, attributes])).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Calls `engine_getPayloadV{1,2,3}` and returns the built execution payload.
+    ///
+    /// The payload is returned as raw JSON so that it can be passed back to
+    /// [`EngineClient::new_payload`] as-is, regardless of its version.
+    pub async fn get_payload(&self, payload_id: PayloadId, timestamp: u64) -> Result<Value> {
+        let version = self.version_at(timestamp);
+        let method = format!("engine_getPayloadV{}", version);
+        let result = self.call(&method, json!([payload_id])).await?;
+
+        // V1 returns the bare payload, later versions wrap it in an envelope.
+        match version {
+            1 => Ok(result),
+            _ => result
+                .get("executionPayload")
+                .cloned()
+                .ok_or(eyre!("Missing execution payload in {} response", method)),
+        }
+    }
+
+    /// Calls `engine_newPayloadV{1,2,3}` to insert the given execution payload.
+    pub async fn new_payload(
+        &self,
+        payload: Value,
+        parent_beacon_block_root: Option<B256>,
+        timestamp: u64,
+    ) -> Result<PayloadStatus> {
+        let version = self.version_at(timestamp);
+        let method = format!("engine_newPayloadV{}", version);
+        let params = match version {
+            // L2 blocks never contain blob transactions, so the versioned hashes are empty.
+            3 => json!([payload, Vec::<B256>::new(), parent_beacon_block_root.unwrap_or_default()]),
+            _ => json!([payload]),
+        };
+
+        let result = self.call(&method, params).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Returns the engine API method version to use at the given L2 timestamp.
+    fn version_at(&self, timestamp: u64) -> u8 {
+        if self.cfg.is_ecotone_active(timestamp) {
+            3
+        } else if self.cfg.is_canyon_active(timestamp) {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Sends an authenticated JSON-RPC request to the engine API and returns its result.
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request_body = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        });
+
+        let claims = Claims::default();
+        let jwt = self.jwt_secret.encode(&claims)?;
+
+        trace!(%method, "Sending engine API request");
+        let response = self
+            .client
+            .post(self.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(AUTHORIZATION, format!("Bearer {}", jwt))
+            .json(&request_body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if let Err(err) = response.error_for_status_ref() {
+            error!("Engine API returned status: {}", status);
+            return Err(err).wrap_err(format!("Engine API call {} failed", method));
+        }
+
+        let mut body = response.json::<Value>().await?;
+        if let Some(err) = body.get("error") {
+            bail!("Engine API call {} failed: {}", method, err);
+        }
+
+        body.get_mut("result").map(Value::take).ok_or(eyre!("Missing result in {}", method))
+    }
+}
+
+/// Converts the execution payload of an unsafe [ExecutionPayloadEnvelope] into its
+/// engine API JSON representation, to be passed to [`EngineClient::new_payload`].
+pub fn envelope_to_payload_json(envelope: &ExecutionPayloadEnvelope) -> Value {
+    let payload = &envelope.payload;
+    let mut json = json!({
+        "parentHash": payload.parent_hash,
+        "feeRecipient": payload.fee_recipient,
+        "stateRoot": payload.state_root,
+        "receiptsRoot": payload.receipts_root,
+        "logsBloom": payload.logs_bloom,
+        "prevRandao": payload.prev_randao,
+        "blockNumber": U64::from(payload.block_number),
+        "gasLimit": U64::from(payload.gas_limit),
+        "gasUsed": U64::from(payload.gas_used),
+        "timestamp": U64::from(payload.timestamp),
+        "extraData": payload.extra_data,
+        "baseFeePerGas": U256::from(payload.base_fee_per_gas.unwrap_or_default()),
+        "blockHash": payload.block_hash,
+        "transactions": payload.transactions,
+    });
+
+    if let Some(withdrawals) = &payload.withdrawals {
+        json["withdrawals"] = json!(withdrawals);
+    }
+    if let Some(blob_gas_used) = payload.blob_gas_used {
+        json["blobGasUsed"] = json!(U64::from(blob_gas_used));
+    }
+    if let Some(excess_blob_gas) = payload.excess_blob_gas {
+        json["excessBlobGas"] = json!(U64::from(excess_blob_gas));
+    }
+
+    json
+}
+
+/// EngineController
+///
+/// Drives an L2 execution client through the engine API, acting as its consensus
+/// client. Instead of only checking that derived attributes match an existing block,
+/// every derived payload is built and inserted into the execution client:
+///
+/// 1. `engine_forkchoiceUpdated` with the derived payload attributes,
+/// 2. `engine_getPayload` to fetch the payload built by the execution client,
+/// 3. `engine_newPayload` to insert it,
+/// 4. `engine_forkchoiceUpdated` to make it the new head of the chain.
+///
+/// Derived payloads are safe by definition, so both the unsafe and safe heads of the
+/// execution client are advanced to the inserted block. The finalized head is advanced
+/// separately, once the L1 origin of a derived block is finalized on L1.
+///
+/// Unsafe payloads received from the p2p network are inserted ahead of the safe head.
+/// Like op-node, derived attributes are first compared with the unsafe block at the same
+/// height: if the block was built from the same attributes on top of the same parent, it
+/// is consolidated by only advancing the safe head. Otherwise, the derived payload is built
+/// and the unsafe chain is reorged out in favor of the derived one.
+///
+/// The unsafe chain itself is tracked by the driver: the controller only keeps the
+/// forkchoice state it sent to the execution client.
+#[derive(Debug)]
+pub struct EngineController {
+    /// The engine API client.
+    client: EngineClient,
+    /// The L2 RPC of the execution client, used to compare derived attributes
+    /// with the unsafe blocks it already has.
+    l2: TrustedValidator,
+    /// The state of the execution client, as driven by the controller.
+    state: Mutex<EngineState>,
+}
+
+/// The state of the execution client driven by an [`EngineController`].
+#[derive(Debug, Default)]
+struct EngineState {
+    /// The last forkchoice state sent to the execution client.
+    forkchoice: ForkchoiceState,
+}
+
+impl EngineController {
+    /// Creates a new [`EngineController`] with the given [`EngineClient`] and a
+    /// [`TrustedValidator`] reading from the L2 RPC of the same execution client.
+    pub fn new(client: EngineClient, l2: TrustedValidator) -> Self {
+        Self { client, l2, state: Mutex::new(EngineState::default()) }
+    }
+
+    /// Returns the last forkchoice state sent to the execution client.
+    pub async fn forkchoice(&self) -> ForkchoiceState {
+        self.state.lock().await.forkchoice
+    }
+
+    /// Returns the hash of the block of the execution client at the height of the given
+    /// attributes, if it extends their parent and was built from the same attributes.
+    async fn matching_block(
+        &self,
+        attributes: &OptimismAttributesWithParent,
+    ) -> Result<Option<B256>> {
+        let number = attributes.parent.block_info.number + 1;
+        let Some((header, transactions)) = self.l2.find_block(number.into()).await? else {
+            return Ok(None);
+        };
+
+        let parent_hash = attributes.parent.block_info.hash;
+        if header.parent_hash != parent_hash ||
+            self.l2.payload_from_block(&header, transactions) != attributes.attributes
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(header.hash))
+    }
+}
+
+#[async_trait]
+impl AttributesValidator for EngineController {
+    async fn validate(&self, attributes: &OptimismAttributesWithParent) -> Result<bool> {
+        let timestamp = attributes.attributes.payload_attributes.timestamp;
+        let number = attributes.parent.block_info.number + 1;
+        let mut engine = self.state.lock().await;
+
+        // If the head of the execution client is ahead of the parent of the derived
+        // attributes, the block may already be part of the unsafe chain.
+        if engine.forkchoice.head_block_hash != attributes.parent.block_info.hash {
+            if let Some(block_hash) = self.matching_block(attributes).await? {
+                let mut state = engine.forkchoice;
+                state.safe_block_hash = block_hash;
+                if state.head_block_hash.is_zero() {
+                    state.head_block_hash = block_hash;
+                }
+
+                let updated = self.client.forkchoice_updated(state, None, timestamp).await?;
+                if !updated.payload_status.status.is_valid() {
+                    error!(status = ?updated.payload_status, "Forkchoice update to safe head failed");
+                    return Ok(false);
+                }
+
+                debug!(%block_hash, "Consolidated derived payload with the unsafe chain");
+                engine.forkchoice = state;
+                return Ok(true);
+            }
+        }
+
+        // Build the new payload on top of the parent of the derived attributes.
+        let unsafe_head = engine.forkchoice.head_block_hash;
+        let mut state = engine.forkchoice;
+        state.head_block_hash = attributes.parent.block_info.hash;
+        if state.safe_block_hash.is_zero() {
+            state.safe_block_hash = state.head_block_hash;
+        }
+
+        let updated =
+            self.client.forkchoice_updated(state, Some(&attributes.attributes), timestamp).await?;
+        if !updated.payload_status.status.is_valid() {
+            error!(status = ?updated.payload_status, "Forkchoice update with attributes failed");
+            return Ok(false);
+        }
+        let Some(payload_id) = updated.payload_id else {
+            bail!("Engine API did not return a payload ID");
+        };
+
+        let payload = self.client.get_payload(payload_id, timestamp).await?;
+        let block_hash = payload
+            .get("blockHash")
+            .cloned()
+            .map(serde_json::from_value::<B256>)
+            .transpose()?
+            .ok_or(eyre!("Missing block hash in execution payload"))?;
+
+        let parent_beacon_block_root =
+            attributes.attributes.payload_attributes.parent_beacon_block_root;
+        let status = self.client.new_payload(payload, parent_beacon_block_root, timestamp).await?;
+        if !status.status.is_valid() {
+            error!(?status, "Engine API rejected the derived payload");
+            return Ok(false);
+        }
+
+        // Derived payloads are safe: advance both heads to the derived block, reorging
+        // out the unsafe chain that did not match the derived attributes, if any.
+        state.safe_block_hash = block_hash;
+        state.head_block_hash = block_hash;
+        if !unsafe_head.is_zero() && unsafe_head != attributes.parent.block_info.hash {
+            warn!(%unsafe_head, %block_hash, "Derived block {} reorgs out the unsafe chain", number);
+        }
+
+        let updated = self.client.forkchoice_updated(state, None, timestamp).await?;
+        if !updated.payload_status.status.is_valid() {
+            error!(status = ?updated.payload_status, "Forkchoice update to new head failed");
+            return Ok(false);
+        }
+
+        debug!(%block_hash, "Inserted derived payload into the execution client");
+        engine.forkchoice = state;
+        Ok(true)
+    }
+
+    async fn validate_unsafe_payload(
+        &self,
+        envelope: &ExecutionPayloadEnvelope,
+    ) -> Result<UnsafePayloadStatus> {
+        let payload = &envelope.payload;
+        let mut engine = self.state.lock().await;
+
+        // Like op-node, payloads the execution client cannot validate yet (SYNCING or
+        // ACCEPTED) still become its head, so that it syncs towards the unsafe chain.
+        let status = self
+            .client
+            .new_payload(
+                envelope_to_payload_json(envelope),
+                envelope.parent_beacon_block_root,
+                payload.timestamp,
+            )
+            .await?;
+        if status.status.is_invalid() {
+            warn!(?status, "Engine API rejected unsafe payload {}", payload.block_number);
+            return Ok(UnsafePayloadStatus::Invalid);
+        }
+
+        let mut state = engine.forkchoice;
+        state.head_block_hash = payload.block_hash;
+        let updated = self.client.forkchoice_updated(state, None, payload.timestamp).await?;
+        if updated.payload_status.status.is_invalid() {
+            warn!(status = ?updated.payload_status, "Forkchoice update to unsafe head failed");
+            return Ok(UnsafePayloadStatus::Invalid);
+        }
+        engine.forkchoice = state;
+
+        if !updated.payload_status.status.is_valid() {
+            debug!(status = ?updated.payload_status, "Execution client is syncing to unsafe payload {}", payload.block_number);
+            return Ok(UnsafePayloadStatus::Unknown);
+        }
+
+        debug!(block_hash = %payload.block_hash, "Inserted unsafe payload into the execution client");
+        Ok(UnsafePayloadStatus::Valid)
+    }
+
+    async fn finalize(&self, block: &L2BlockInfo) -> Result<()> {
+        let mut engine = self.state.lock().await;
+        if engine.forkchoice.head_block_hash.is_zero() {
+            // Nothing has been inserted yet, so there is no forkchoice to update.
+            return Ok(());
+        }
+
+        let mut state = engine.forkchoice;
+        state.finalized_block_hash = block.block_info.hash;
+        let updated =
+            self.client.forkchoice_updated(state, None, block.block_info.timestamp).await?;
+        if !updated.payload_status.status.is_valid() {
+            bail!("Forkchoice update to finalized head failed: {:?}", updated.payload_status);
+        }
+
+        engine.forkchoice = state;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        primitives::{address, Signature},
+        rpc::types::{engine::PayloadAttributes, Block, BlockTransactions, Header},
+    };
+    use jsonrpsee::{
+        server::{Server, ServerHandle},
+        types::ErrorObjectOwned,
+        RpcModule,
+    };
+    use kona_primitives::L2ExecutionPayload;
+    use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+    use op_net::types::payload::PayloadHash;
+    use std::sync::Mutex as StdMutex;
+
+    const BUILT_BLOCK_HASH: B256 = B256::repeat_byte(0xbb);
+
+    /// Starts a mock engine API server that records every method call.
+    ///
+    /// New payloads and forkchoice updates without attributes are answered with the
+    /// given status. The server also serves the given L2 block, if any, on the L2 RPC.
+    async fn mock_engine(
+        status: &'static str,
+        l2_block: Option<Value>,
+    ) -> (Url, ServerHandle, Arc<StdMutex<Vec<String>>>) {
+        let calls = Arc::new(StdMutex::new(Vec::new()));
+        let mut module = RpcModule::new(calls.clone());
+
+        module
+            .register_method("eth_getBlockByNumber", move |_, calls, _| {
+                calls.lock().unwrap().push("getBlockByNumber".to_string());
+                Ok::<_, ErrorObjectOwned>(l2_block.clone())
+            })
+            .unwrap();
+
+        module
+            .register_method("engine_forkchoiceUpdatedV3", move |params, calls, _| {
+                let params: Value = params.parse()?;
+                let with_attributes = !params[1].is_null();
+                calls.lock().unwrap().push(format!("fcu(attributes={})", with_attributes));
+                let status = if with_attributes { "VALID" } else { status };
+                Ok::<_, ErrorObjectOwned>(json!({
+                    "payloadStatus": { "status": status, "latestValidHash": null },
+                    "payloadId": with_attributes.then_some("0x0000000000000001"),
+                }))
+            })
+            .unwrap();
+        module
+            .register_method("engine_getPayloadV3", |_, calls, _| {
+                calls.lock().unwrap().push("getPayload".to_string());
+                Ok::<_, ErrorObjectOwned>(json!({
+                    "executionPayload": { "blockHash": BUILT_BLOCK_HASH },
+                    "blockValue": "0x0",
+                }))
+            })
+            .unwrap();
+        module
+            .register_method("engine_newPayloadV3", move |params, calls, _| {
+                let params: Value = params.parse()?;
+                calls.lock().unwrap().push(format!("newPayload({})", params[0]["blockHash"]));
+                Ok::<_, ErrorObjectOwned>(json!({
+                    "status": status,
+                    "latestValidHash": null,
+                }))
+            })
+            .unwrap();
+
+        let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", server.local_addr().unwrap())).unwrap();
+        (url, server.start(module), calls)
+    }
+
+    fn controller(url: Url) -> EngineController {
+        let cfg = Arc::new(RollupConfig { ecotone_time: Some(0), ..Default::default() });
+        let l2 = TrustedValidator::new_http(url.clone(), 0);
+        EngineController::new(EngineClient::new_http(url, JwtSecret::random(), cfg), l2)
+    }
+
+    /// Returns the L2 block 11 with the given hash and timestamp, built on top of the
+    /// parent of [attributes] with the same fields, except for the timestamp.
+    fn l2_block(hash: B256, timestamp: u64) -> Value {
+        let header = Header {
+            hash,
+            parent_hash: B256::repeat_byte(0xaa),
+            number: 11,
+            timestamp,
+            miner: address!("4200000000000000000000000000000000000011"),
+            mix_hash: Some(B256::ZERO),
+            gas_limit: 30_000_000,
+            parent_beacon_block_root: Some(B256::ZERO),
+            ..Default::default()
+        };
+        let block: Block = Block {
+            header,
+            uncles: Vec::new(),
+            transactions: BlockTransactions::Hashes(Vec::new()),
+            size: None,
+            withdrawals: None,
+        };
+        serde_json::to_value(block).unwrap()
+    }
+
+    fn attributes() -> OptimismAttributesWithParent {
+        let parent = L2BlockInfo {
+            block_info: BlockInfo {
+                number: 10,
+                hash: B256::repeat_byte(0xaa),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let attributes = OptimismPayloadAttributes {
+            payload_attributes: PayloadAttributes {
+                timestamp: 100,
+                prev_randao: B256::ZERO,
+                suggested_fee_recipient: address!("4200000000000000000000000000000000000011"),
+                withdrawals: Some(vec![]),
+                parent_beacon_block_root: Some(B256::ZERO),
+            },
+            transactions: Some(vec![]),
+            no_tx_pool: Some(true),
+            gas_limit: Some(30_000_000),
+        };
+        OptimismAttributesWithParent::new(attributes, parent, false)
+    }
+
+    fn unsafe_envelope(number: u64, hash: B256) -> ExecutionPayloadEnvelope {
+        let mut payload: L2ExecutionPayload = Default::default();
+        payload.block_number = number;
+        payload.block_hash = hash;
+        payload.timestamp = 100 + number;
+        ExecutionPayloadEnvelope {
+            payload,
+            signature: Signature::test_signature(),
+            hash: PayloadHash::from(hash.as_slice()),
+            parent_beacon_block_root: Some(B256::ZERO),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_insert_derived_payload() {
+        let (url, _handle, calls) = mock_engine("VALID", None).await;
+        let controller = controller(url);
+
+        assert!(controller.validate(&attributes()).await.unwrap());
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "getBlockByNumber".to_string(),
+                "fcu(attributes=true)".to_string(),
+                "getPayload".to_string(),
+                format!("newPayload(\"{}\")", BUILT_BLOCK_HASH),
+                "fcu(attributes=false)".to_string(),
+            ]
+        );
+
+        let forkchoice = controller.forkchoice().await;
+        assert_eq!(forkchoice.head_block_hash, BUILT_BLOCK_HASH);
+        assert_eq!(forkchoice.safe_block_hash, BUILT_BLOCK_HASH);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_payload_does_not_advance_head() {
+        let (url, _handle, calls) = mock_engine("INVALID", None).await;
+        let controller = controller(url);
+
+        assert!(!controller.validate(&attributes()).await.unwrap());
+        assert_eq!(calls.lock().unwrap().len(), 4);
+        assert_eq!(controller.forkchoice().await, ForkchoiceState::default());
+    }
+
+    #[tokio::test]
+    async fn test_derived_payload_matching_unsafe_chain_keeps_unsafe_head() {
+        let (url, _handle, calls) =
+            mock_engine("VALID", Some(l2_block(BUILT_BLOCK_HASH, 100))).await;
+        let controller = controller(url);
+        let unsafe_tip = B256::repeat_byte(0xcc);
+
+        for envelope in [unsafe_envelope(11, BUILT_BLOCK_HASH), unsafe_envelope(12, unsafe_tip)] {
+            let status = controller.validate_unsafe_payload(&envelope).await.unwrap();
+            assert_eq!(status, UnsafePayloadStatus::Valid);
+        }
+        assert_eq!(controller.forkchoice().await.head_block_hash, unsafe_tip);
+        assert_eq!(
+            calls.lock().unwrap()[..2],
+            [format!("newPayload(\"{}\")", BUILT_BLOCK_HASH), "fcu(attributes=false)".to_string()]
+        );
+
+        // The derived block 11 matches the unsafe chain: it is consolidated without
+        // building a new payload, and only the safe head moves.
+        calls.lock().unwrap().clear();
+        assert!(controller.validate(&attributes()).await.unwrap());
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["getBlockByNumber".to_string(), "fcu(attributes=false)".to_string()]
+        );
+        let forkchoice = controller.forkchoice().await;
+        assert_eq!(forkchoice.head_block_hash, unsafe_tip);
+        assert_eq!(forkchoice.safe_block_hash, BUILT_BLOCK_HASH);
+    }
+
+    #[tokio::test]
+    async fn test_derived_payload_reorgs_conflicting_unsafe_chain() {
+        // The unsafe block 11 was built with a different timestamp.
+        let conflicting = B256::repeat_byte(0xcc);
+        let (url, _handle, calls) = mock_engine("VALID", Some(l2_block(conflicting, 101))).await;
+        let controller = controller(url);
+
+        for envelope in [unsafe_envelope(11, conflicting), unsafe_envelope(12, conflicting)] {
+            let status = controller.validate_unsafe_payload(&envelope).await.unwrap();
+            assert_eq!(status, UnsafePayloadStatus::Valid);
+        }
+
+        // The derived block 11 conflicts with the unsafe chain, which is reorged out.
+        assert!(controller.validate(&attributes()).await.unwrap());
+        assert!(calls.lock().unwrap().contains(&"getPayload".to_string()));
+        let forkchoice = controller.forkchoice().await;
+        assert_eq!(forkchoice.head_block_hash, BUILT_BLOCK_HASH);
+        assert_eq!(forkchoice.safe_block_hash, BUILT_BLOCK_HASH);
+    }
+
+    #[tokio::test]
+    async fn test_syncing_unsafe_payload_is_unknown() {
+        let (url, _handle, _calls) = mock_engine("SYNCING", None).await;
+        let controller = controller(url);
+
+        let envelope = unsafe_envelope(11, BUILT_BLOCK_HASH);
+        let status = controller.validate_unsafe_payload(&envelope).await.unwrap();
+        assert_eq!(status, UnsafePayloadStatus::Unknown);
+        // The execution client still syncs towards the unsafe payload.
+        assert_eq!(controller.forkchoice().await.head_block_hash, BUILT_BLOCK_HASH);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_unsafe_payload_is_rejected() {
+        let (url, _handle, calls) = mock_engine("INVALID", None).await;
+        let controller = controller(url);
+
+        let envelope = unsafe_envelope(11, BUILT_BLOCK_HASH);
+        let status = controller.validate_unsafe_payload(&envelope).await.unwrap();
+        assert_eq!(status, UnsafePayloadStatus::Invalid);
+        assert_eq!(calls.lock().unwrap().len(), 1);
+        assert_eq!(controller.forkchoice().await, ForkchoiceState::default());
+    }
+}
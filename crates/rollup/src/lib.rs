@@ -5,13 +5,27 @@
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
 mod driver;
-pub use driver::Driver;
+pub use driver::{Driver, DriverEvent, L2Heads};
 
 mod cli;
 pub use cli::HeraArgsExt;
 
 mod validator;
-pub use validator::AttributesValidator;
+pub use validator::{AttributesValidator, TrustedValidator, UnsafePayloadStatus};
+
+mod engine;
+pub use engine::{EngineClient, EngineController};
+
+mod retry;
+pub use retry::{RetryPolicy, RetryingValidator};
+
+mod output;
+pub use output::{
+    OutputReference, OutputRoot, OutputVerification, OutputVerifier, L2_TO_L1_MESSAGE_PASSER,
+};
+
+mod rpc;
+pub use rpc::{OutputResponse, RollupNodeApiServer, RollupNodeRpc};
 
 mod pipeline;
 pub use pipeline::{new_rollup_pipeline, RollupPipeline};
@@ -0,0 +1,341 @@
+//! L2 output roots.
+
+use alloy::{
+    primitives::{address, keccak256, Address, B256, U256, U64},
+    providers::{network::primitives::BlockTransactionsKind, Provider, ReqwestProvider},
+    sol,
+};
+use eyre::{eyre, Result};
+use url::Url;
+
+use crate::OutputResponse;
+
+sol! {
+    /// The subset of the `L2OutputOracle` contract used to fetch proposed output roots.
+    #[sol(rpc)]
+    interface L2OutputOracle {
+        /// An output root proposal.
+        struct OutputProposal {
+            bytes32 outputRoot;
+            uint128 timestamp;
+            uint128 l2BlockNumber;
+        }
+
+        function latestBlockNumber() external view returns (uint256);
+        function getL2OutputIndexAfter(uint256 _l2BlockNumber) external view returns (uint256);
+        function getL2Output(uint256 _l2OutputIndex) external view returns (OutputProposal memory);
+    }
+}
+
+/// The address of the `L2ToL1MessagePasser` predeploy, whose storage root
+/// commits to all the withdrawals initiated on L2.
+pub const L2_TO_L1_MESSAGE_PASSER: Address = address!("4200000000000000000000000000000000000016");
+
+/// The version of the output root format implemented by [OutputRoot].
+pub const OUTPUT_ROOT_VERSION: B256 = B256::ZERO;
+
+/// The components of a version 0 L2 output root.
+///
+/// Ref: <https://specs.optimism.io/protocol/proposals.html#l2-output-commitment-construction>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputRoot {
+    /// The state root of the L2 block.
+    pub state_root: B256,
+    /// The storage root of the `L2ToL1MessagePasser` predeploy at the L2 block.
+    pub withdrawal_storage_root: B256,
+    /// The hash of the L2 block.
+    pub block_hash: B256,
+}
+
+impl OutputRoot {
+    /// Creates a new [OutputRoot] from its components.
+    pub const fn new(state_root: B256, withdrawal_storage_root: B256, block_hash: B256) -> Self {
+        Self { state_root, withdrawal_storage_root, block_hash }
+    }
+
+    /// Fetches the components of the output root at the given L2 block number
+    /// from an L2 execution client.
+    ///
+    /// The proof is requested by block hash, so that both the block and the proof
+    /// refer to the same block even if the L2 chain reorgs in between.
+    pub async fn fetch(provider: &ReqwestProvider, number: u64) -> Result<Self> {
+        let block = provider
+            .get_block(number.into(), BlockTransactionsKind::Hashes)
+            .await
+            .map_err(|e| eyre!(format!("Failed to fetch block: {:?}", e)))?
+            .ok_or(eyre!("Block not found"))?;
+
+        let proof = provider
+            .get_proof(L2_TO_L1_MESSAGE_PASSER, Vec::new())
+            .block_id(block.header.hash.into())
+            .await
+            .map_err(|e| eyre!(format!("Failed to fetch message passer proof: {:?}", e)))?;
+
+        Ok(Self::new(block.header.state_root, proof.storage_hash, block.header.hash))
+    }
+
+    /// Returns the output root hash:
+    /// `keccak256(version ++ state_root ++ withdrawal_storage_root ++ block_hash)`.
+    pub fn hash(&self) -> B256 {
+        let mut preimage = [0u8; 128];
+        preimage[..32].copy_from_slice(OUTPUT_ROOT_VERSION.as_slice());
+        preimage[32..64].copy_from_slice(self.state_root.as_slice());
+        preimage[64..96].copy_from_slice(self.withdrawal_storage_root.as_slice());
+        preimage[96..].copy_from_slice(self.block_hash.as_slice());
+        keccak256(preimage)
+    }
+}
+
+/// A reference source of L2 output roots to verify derived blocks against.
+#[derive(Debug, Clone)]
+pub enum OutputReference {
+    /// A trusted rollup node, queried with `optimism_outputAtBlock`.
+    RollupNode(ReqwestProvider),
+    /// An `L2OutputOracle` contract on L1, which only holds output roots for
+    /// the L2 blocks that have been proposed.
+    L2OutputOracle {
+        /// The L1 provider.
+        provider: ReqwestProvider,
+        /// The address of the `L2OutputOracle` contract.
+        address: Address,
+        /// The next known proposal, as an (L2 block number, output root) tuple.
+        next_proposal: Option<(u64, B256)>,
+        /// The L2 block number of the latest proposal, as last returned by the oracle.
+        latest_block: Option<u64>,
+    },
+}
+
+impl OutputReference {
+    /// Creates an [OutputReference] backed by a trusted rollup node at the given [Url].
+    pub fn rollup_node(url: Url) -> Self {
+        Self::RollupNode(ReqwestProvider::new_http(url))
+    }
+
+    /// Creates an [OutputReference] backed by the `L2OutputOracle` contract at the given
+    /// address, on the L1 chain at the given [Url].
+    pub fn l2_output_oracle(l1_rpc_url: Url, address: Address) -> Self {
+        Self::L2OutputOracle {
+            provider: ReqwestProvider::new_http(l1_rpc_url),
+            address,
+            next_proposal: None,
+            latest_block: None,
+        }
+    }
+
+    /// Returns the reference output root at the given L2 block number,
+    /// or `None` if the reference has no output root for this block.
+    pub async fn output_root(&mut self, number: u64) -> Result<Option<B256>> {
+        match self {
+            Self::RollupNode(provider) => {
+                let output: OutputResponse = provider
+                    .raw_request("optimism_outputAtBlock".into(), [U64::from(number)])
+                    .await
+                    .map_err(|e| eyre!(format!("Failed to fetch reference output: {:?}", e)))?;
+                Ok(Some(output.output_root))
+            }
+            Self::L2OutputOracle { provider, address, next_proposal, latest_block } => {
+                // Avoid querying the oracle until the next known proposal is reached.
+                if let Some((proposal_block, output_root)) = *next_proposal {
+                    if number < proposal_block {
+                        return Ok(None);
+                    }
+                    if number == proposal_block {
+                        return Ok(Some(output_root));
+                    }
+                }
+
+                // The latest proposal only needs to be queried again once it is passed.
+                let oracle = L2OutputOracle::new(*address, &*provider);
+                if latest_block.map_or(true, |latest| number > latest) {
+                    let latest = oracle.latestBlockNumber().call().await?._0;
+                    *latest_block = Some(latest.saturating_to::<u64>());
+                    if U256::from(number) > latest {
+                        return Ok(None);
+                    }
+                }
+
+                let index = oracle.getL2OutputIndexAfter(U256::from(number)).call().await?._0;
+                let proposal = oracle.getL2Output(index).call().await?._0;
+                let proposal_block = proposal.l2BlockNumber.to::<u64>();
+                *next_proposal = Some((proposal_block, proposal.outputRoot));
+
+                Ok((proposal_block == number).then_some(proposal.outputRoot))
+            }
+        }
+    }
+}
+
+/// The result of verifying the output root of a derived L2 block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputVerification {
+    /// The computed output root matches the reference.
+    Match(B256),
+    /// The computed output root differs from the reference.
+    Mismatch {
+        /// The reference output root.
+        expected: B256,
+        /// The output root computed from the L2 execution client.
+        actual: B256,
+    },
+    /// The reference has no output root for this block.
+    Unavailable(B256),
+}
+
+impl OutputVerification {
+    /// Compares the computed output root with the reference one, if any.
+    pub fn new(expected: Option<B256>, actual: B256) -> Self {
+        match expected {
+            Some(expected) if expected == actual => Self::Match(actual),
+            Some(expected) => Self::Mismatch { expected, actual },
+            None => Self::Unavailable(actual),
+        }
+    }
+}
+
+/// Computes the output roots of derived L2 blocks from the L2 execution client,
+/// and verifies them against an [OutputReference].
+#[derive(Debug, Clone)]
+pub struct OutputVerifier {
+    /// The L2 execution client provider.
+    l2_provider: ReqwestProvider,
+    /// The reference source of output roots.
+    reference: OutputReference,
+}
+
+impl OutputVerifier {
+    /// Creates a new [OutputVerifier] for the L2 execution client at the given [Url].
+    pub fn new_http(l2_rpc_url: Url, reference: OutputReference) -> Self {
+        Self { l2_provider: ReqwestProvider::new_http(l2_rpc_url), reference }
+    }
+
+    /// Computes the output root at the given L2 block number and verifies it.
+    pub async fn verify(&mut self, number: u64) -> Result<OutputVerification> {
+        let actual = OutputRoot::fetch(&self.l2_provider, number).await?.hash();
+        let expected = self.reference.output_root(number).await?;
+        Ok(OutputVerification::new(expected, actual))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{primitives::Bytes, sol_types::SolCall};
+    use jsonrpsee::{
+        server::{Server, ServerHandle},
+        types::ErrorObjectOwned,
+        RpcModule,
+    };
+    use serde_json::Value;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn test_output_root_hash() {
+        let output = OutputRoot::new(
+            B256::repeat_byte(0x01),
+            B256::repeat_byte(0x02),
+            B256::repeat_byte(0x03),
+        );
+
+        let preimage = [[0u8; 32], [0x01; 32], [0x02; 32], [0x03; 32]].concat();
+        assert_eq!(output.hash(), keccak256(preimage));
+
+        // Each component is committed to.
+        let other = OutputRoot { block_hash: B256::repeat_byte(0x04), ..output };
+        assert_ne!(output.hash(), other.hash());
+    }
+
+    #[test]
+    fn test_output_verification() {
+        let actual = B256::repeat_byte(0x01);
+        let other = B256::repeat_byte(0x02);
+
+        assert_eq!(
+            OutputVerification::new(Some(actual), actual),
+            OutputVerification::Match(actual)
+        );
+        assert_eq!(
+            OutputVerification::new(Some(other), actual),
+            OutputVerification::Mismatch { expected: other, actual }
+        );
+        assert_eq!(OutputVerification::new(None, actual), OutputVerification::Unavailable(actual));
+    }
+
+    #[tokio::test]
+    async fn test_oracle_is_not_queried_before_next_proposal() {
+        let proposal = B256::repeat_byte(0x01);
+        let mut reference = OutputReference::L2OutputOracle {
+            // Nothing listens on this address: any query would fail.
+            provider: ReqwestProvider::new_http("http://127.0.0.1:1".parse().unwrap()),
+            address: Address::ZERO,
+            next_proposal: Some((120, proposal)),
+            latest_block: None,
+        };
+
+        assert_eq!(reference.output_root(100).await.unwrap(), None);
+        assert_eq!(reference.output_root(120).await.unwrap(), Some(proposal));
+        assert!(reference.output_root(121).await.is_err());
+    }
+
+    /// Starts a mock L1 RPC serving an `L2OutputOracle` with a proposal every 120 L2
+    /// blocks up to the given latest block, and counting the `latestBlockNumber` calls.
+    async fn mock_oracle(latest: u64) -> (Url, ServerHandle, Arc<AtomicUsize>) {
+        let latest_calls = Arc::new(AtomicUsize::new(0));
+        let mut module = RpcModule::new(latest_calls.clone());
+        module
+            .register_method("eth_call", move |params, latest_calls, _| {
+                let params: Value = params.parse()?;
+                let request = params[0].get("input").or(params[0].get("data")).cloned();
+                let input: Bytes = serde_json::from_value(request.unwrap_or_default()).unwrap();
+                let arg = (input.len() >= 36).then(|| U256::from_be_slice(&input[4..36]));
+
+                let output = match input[..4].try_into().unwrap() {
+                    L2OutputOracle::latestBlockNumberCall::SELECTOR => {
+                        latest_calls.fetch_add(1, Ordering::SeqCst);
+                        L2OutputOracle::latestBlockNumberCall::abi_encode_returns(&(U256::from(
+                            latest,
+                        ),))
+                    }
+                    L2OutputOracle::getL2OutputIndexAfterCall::SELECTOR => {
+                        let number = arg.unwrap().to::<u64>();
+                        let index = U256::from(number.div_ceil(120) - 1);
+                        L2OutputOracle::getL2OutputIndexAfterCall::abi_encode_returns(&(index,))
+                    }
+                    L2OutputOracle::getL2OutputCall::SELECTOR => {
+                        let index = arg.unwrap().to::<u64>();
+                        let proposal = L2OutputOracle::OutputProposal {
+                            outputRoot: B256::repeat_byte(index as u8 + 1),
+                            timestamp: 0,
+                            l2BlockNumber: (index as u128 + 1) * 120,
+                        };
+                        L2OutputOracle::getL2OutputCall::abi_encode_returns(&(proposal,))
+                    }
+                    selector => panic!("unexpected call: {:?}", selector),
+                };
+                Ok::<_, ErrorObjectOwned>(Bytes::from(output))
+            })
+            .unwrap();
+
+        let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", server.local_addr().unwrap())).unwrap();
+        (url, server.start(module), latest_calls)
+    }
+
+    #[tokio::test]
+    async fn test_oracle_latest_block_is_cached_until_passed() {
+        let (url, _handle, latest_calls) = mock_oracle(240).await;
+        let mut reference = OutputReference::l2_output_oracle(url, Address::ZERO);
+
+        assert_eq!(reference.output_root(100).await.unwrap(), None);
+        assert_eq!(reference.output_root(120).await.unwrap(), Some(B256::repeat_byte(1)));
+        assert_eq!(reference.output_root(121).await.unwrap(), None);
+        assert_eq!(reference.output_root(240).await.unwrap(), Some(B256::repeat_byte(2)));
+        assert_eq!(latest_calls.load(Ordering::SeqCst), 1);
+
+        // Past the latest proposal, the oracle is queried for new proposals.
+        assert_eq!(reference.output_root(241).await.unwrap(), None);
+        assert_eq!(latest_calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -8,7 +8,7 @@ use kona_derive::{
         AttributesQueue, BatchQueue, ChannelBank, ChannelReader, FrameQueue, L1Retrieval,
         L1Traversal, StatefulAttributesBuilder,
     },
-    traits::{BlobProvider, ChainProvider},
+    traits::{BlobProvider, ChainProvider, L2ChainProvider},
 };
 use op_alloy_genesis::RollupConfig;
 use op_alloy_protocol::BlockInfo;
@@ -27,25 +27,27 @@ type L1AttributesQueue<CP, BP, L2CP> = AttributesQueue<
 /// A derivation pipeline generic over:
 /// - The L1 [ChainProvider] (CP)
 /// - The L1 [BlobProvider] (BP)
+/// - The [L2ChainProvider] (L2CP), an [AlloyL2ChainProvider] by default
 ///
 /// This pipeline is a derivation pipeline that takes the outputs of the [FrameQueue] stage
 /// and transforms them into
 /// [OptimismPayloadAttributes](op_alloy_rpc_types_engine::OptimismPayloadAttributes).
-pub type RollupPipeline<CP, BP> =
-    DerivationPipeline<L1AttributesQueue<CP, BP, AlloyL2ChainProvider>, AlloyL2ChainProvider>;
+pub type RollupPipeline<CP, BP, L2CP = AlloyL2ChainProvider> =
+    DerivationPipeline<L1AttributesQueue<CP, BP, L2CP>, L2CP>;
 
 /// Creates a new [RollupPipeline] from the given components.
 #[allow(unused)]
-pub fn new_rollup_pipeline<CP, BP>(
+pub fn new_rollup_pipeline<CP, BP, L2CP>(
     cfg: Arc<RollupConfig>,
     chain_provider: CP,
     blob_provider: BP,
-    l2_chain_provider: AlloyL2ChainProvider,
+    l2_chain_provider: L2CP,
     origin: BlockInfo,
-) -> RollupPipeline<CP, BP>
+) -> RollupPipeline<CP, BP, L2CP>
 where
     CP: ChainProvider + Send + Sync + Clone + Debug,
     BP: BlobProvider + Send + Sync + Clone + Debug,
+    L2CP: L2ChainProvider + Send + Sync + Clone + Debug,
 {
     let dap = EthereumDataSource::new(chain_provider.clone(), blob_provider.clone(), &cfg.clone());
     let attributes = StatefulAttributesBuilder::new(
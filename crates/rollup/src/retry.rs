@@ -0,0 +1,287 @@
+//! Retry logic for transient RPC failures.
+
+use std::{
+    collections::hash_map::RandomState,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use alloy::transports::{RpcError, TransportError};
+use async_trait::async_trait;
+use eyre::Result;
+use metrics::counter;
+use op_alloy_protocol::L2BlockInfo;
+use op_alloy_rpc_types_engine::OptimismAttributesWithParent;
+use op_net::types::envelope::ExecutionPayloadEnvelope;
+use tracing::{debug, warn};
+
+use crate::{AttributesValidator, UnsafePayloadStatus};
+
+/// Error message fragments that indicate a transient failure when
+/// the underlying error type has been erased.
+///
+/// These only match transport-level failures: HTTP status codes and JSON-RPC
+/// errors are classified from the typed errors instead.
+const TRANSIENT_ERROR_PATTERNS: &[&str] = &[
+    "timed out",
+    "connection refused",
+    "connection reset",
+    "connection closed",
+    "connect error",
+    "broken pipe",
+    "temporarily unavailable",
+];
+
+/// A bounded exponential backoff policy with jitter, used to retry
+/// RPC calls that fail for transient reasons.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    max_attempts: u32,
+    /// The backoff before the first retry.
+    initial_backoff: Duration,
+    /// The upper bound on the backoff between two attempts.
+    max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(5, Duration::from_millis(250), Duration::from_secs(10))
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a new [RetryPolicy].
+    pub const fn new(max_attempts: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self { max_attempts, initial_backoff, max_backoff }
+    }
+
+    /// Sets the maximum number of attempts, including the first one.
+    pub const fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Returns the maximum number of attempts, including the first one.
+    pub const fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the backoff to wait before the given retry attempt (starting at 0).
+    ///
+    /// The backoff doubles at every attempt up to `max_backoff`, and is then
+    /// randomized in the `[backoff / 2, backoff]` range to avoid thundering herds.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.initial_backoff.saturating_mul(2u32.saturating_pow(attempt));
+        let capped = exp.min(self.max_backoff);
+        capped / 2 + capped.mul_f64(jitter() / 2.0)
+    }
+
+    /// Runs the given operation, retrying it with backoff as long as it fails
+    /// with a transient error and the maximum number of attempts is not reached.
+    ///
+    /// Permanent errors are returned immediately.
+    pub async fn retry<T, F, Fut>(&self, operation: &'static str, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let err = match f().await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+
+            let transient = is_transient(&err);
+            attempt += 1;
+            if !transient || attempt >= self.max_attempts {
+                let kind = if transient { "transient" } else { "permanent" };
+                counter!("hera_rpc_failures_total", "operation" => operation, "kind" => kind)
+                    .increment(1);
+                debug!(%operation, %kind, attempt, "RPC call failed: {:?}", err);
+                return Err(err);
+            }
+
+            let backoff = self.backoff(attempt - 1);
+            counter!("hera_rpc_retries_total", "operation" => operation).increment(1);
+            warn!(%operation, attempt, ?backoff, "Transient RPC error, retrying: {}", err);
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+/// An [AttributesValidator] that retries the calls of an inner validator
+/// on transient errors, according to a [RetryPolicy].
+#[derive(Debug)]
+pub struct RetryingValidator {
+    /// The inner validator.
+    inner: Box<dyn AttributesValidator>,
+    /// The retry policy.
+    policy: RetryPolicy,
+}
+
+impl RetryingValidator {
+    /// Creates a new [RetryingValidator] wrapping the given validator.
+    pub fn new(inner: Box<dyn AttributesValidator>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+#[async_trait]
+impl AttributesValidator for RetryingValidator {
+    async fn validate(&self, attributes: &OptimismAttributesWithParent) -> Result<bool> {
+        let inner = &self.inner;
+        self.policy.retry("validate_attributes", move || inner.validate(attributes)).await
+    }
+
+    async fn validate_unsafe_payload(
+        &self,
+        envelope: &ExecutionPayloadEnvelope,
+    ) -> Result<UnsafePayloadStatus> {
+        let inner = &self.inner;
+        self.policy
+            .retry("validate_unsafe_payload", move || inner.validate_unsafe_payload(envelope))
+            .await
+    }
+
+    async fn finalize(&self, block: &L2BlockInfo) -> Result<()> {
+        let inner = &self.inner;
+        self.policy.retry("finalize", move || inner.finalize(block)).await
+    }
+}
+
+/// Returns `true` if the given error is likely to be transient, i.e. if
+/// the same call could succeed when retried later.
+pub fn is_transient(err: &eyre::Report) -> bool {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<TransportError>() {
+            return match err {
+                RpcError::Transport(_) | RpcError::NullResp => true,
+                // Rate limiting and request limits.
+                RpcError::ErrorResp(payload) => matches!(payload.code, 429 | -32005),
+                _ => false,
+            };
+        }
+
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return err.is_timeout() ||
+                err.is_connect() ||
+                err.status().is_some_and(|s| s.is_server_error() || s.as_u16() == 429);
+        }
+
+        if cause.downcast_ref::<std::io::Error>().is_some() {
+            return true;
+        }
+    }
+
+    // Providers may erase the underlying error type, so fall back to its message.
+    let message = err.to_string().to_lowercase();
+    TRANSIENT_ERROR_PATTERNS.iter().any(|pattern| message.contains(pattern))
+}
+
+/// Returns a random number in the `[0, 1]` range.
+fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    random as f64 / u64::MAX as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::transports::TransportErrorKind;
+    use eyre::eyre;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(3, Duration::from_millis(1), Duration::from_millis(4))
+    }
+
+    #[test]
+    fn test_backoff_is_bounded() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100), Duration::from_secs(1));
+        for attempt in 0..32 {
+            let backoff = policy.backoff(attempt);
+            let expected = (Duration::from_millis(100) * 2u32.saturating_pow(attempt))
+                .min(Duration::from_secs(1));
+            assert!(backoff >= expected / 2, "attempt {}: {:?}", attempt, backoff);
+            assert!(backoff <= expected, "attempt {}: {:?}", attempt, backoff);
+        }
+    }
+
+    #[test]
+    fn test_error_classification() {
+        let transport: eyre::Report = TransportErrorKind::custom_str("connection reset").into();
+        assert!(is_transient(&transport));
+
+        let io: eyre::Report = std::io::Error::from(std::io::ErrorKind::ConnectionRefused).into();
+        assert!(is_transient(&io));
+
+        assert!(is_transient(&eyre!("request timed out")));
+        assert!(!is_transient(&eyre!("invalid block hash")));
+        assert!(!is_transient(&eyre!("Block not found")));
+        assert!(!is_transient(&eyre!("invalid block 5030")));
+    }
+
+    #[test]
+    fn test_rpc_error_classification() {
+        let error_resp = |code: i64| -> eyre::Report {
+            let payload = serde_json::from_value(serde_json::json!({
+                "code": code,
+                "message": "error",
+            }))
+            .unwrap();
+            TransportError::ErrorResp(payload).into()
+        };
+
+        assert!(is_transient(&error_resp(429)));
+        assert!(is_transient(&error_resp(-32005)));
+        assert!(!is_transient(&error_resp(-32603)));
+        assert!(!is_transient(&error_resp(-32000)));
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors() {
+        let calls = AtomicU32::new(0);
+        let result = policy()
+            .retry("test", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(eyre!("connection refused")),
+                    _ => Ok(42),
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = policy()
+            .retry("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(eyre!("connection refused"))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_on_permanent_errors() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = policy()
+            .retry("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(eyre!("invalid block hash"))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,270 @@
+//! The rollup node JSON-RPC server, implementing the `optimism_*` namespace.
+
+use std::{
+    fmt::Display,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use alloy::{
+    eips::BlockNumHash,
+    primitives::{B256, U64},
+    providers::ReqwestProvider,
+};
+use async_trait::async_trait;
+use eyre::Result;
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    server::{Server, ServerHandle},
+    types::{error::INTERNAL_ERROR_CODE, ErrorObject, ErrorObjectOwned},
+};
+use kona_derive::{online::AlloyL2ChainProvider, traits::L2ChainProvider};
+use op_alloy_genesis::RollupConfig;
+use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use ser::types::{L1BlockRef, L2BlockRef, SyncStatus};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::info;
+use url::Url;
+
+use crate::output::{OutputRoot, OUTPUT_ROOT_VERSION};
+
+/// The output root at a given L2 block, as returned by `optimism_outputAtBlock`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputResponse {
+    /// The version of the output root.
+    pub version: B256,
+    /// The output root hash.
+    pub output_root: B256,
+    /// The L2 block the output root was computed at.
+    pub block_ref: L2BlockRef,
+    /// The storage root of the `L2ToL1MessagePasser` predeploy at the L2 block.
+    pub withdrawal_storage_root: B256,
+    /// The state root of the L2 block.
+    pub state_root: B256,
+    /// The sync status of the node when the output root was computed.
+    pub sync_status: SyncStatus,
+}
+
+/// The `optimism_*` JSON-RPC namespace exposed by rollup nodes.
+///
+/// Ref: <https://docs.optimism.io/builders/node-operators/json-rpc>
+#[rpc(server, namespace = "optimism")]
+pub trait RollupNodeApi {
+    /// Returns the current sync status of the node.
+    #[method(name = "syncStatus")]
+    async fn sync_status(&self) -> RpcResult<SyncStatus>;
+
+    /// Returns the rollup configuration of the node.
+    #[method(name = "rollupConfig")]
+    async fn rollup_config(&self) -> RpcResult<RollupConfig>;
+
+    /// Returns the output root at the given L2 block number.
+    #[method(name = "outputAtBlock")]
+    async fn output_at_block(&self, block_number: U64) -> RpcResult<OutputResponse>;
+
+    /// Returns the version of the node.
+    #[method(name = "version")]
+    async fn version(&self) -> RpcResult<String>;
+
+    /// Returns whether the node is connected to its L1 node.
+    ///
+    /// Nodes that don't maintain a connection to the L1 node always report `true`.
+    #[method(name = "l1Connected")]
+    async fn l1_connected(&self) -> RpcResult<bool>;
+}
+
+/// The rollup node JSON-RPC server.
+///
+/// The sync status is published by the driver through a [watch] channel, while
+/// output roots are computed from the state of the L2 execution client.
+#[derive(Debug, Clone)]
+pub struct RollupNodeRpc {
+    /// The rollup configuration.
+    cfg: Arc<RollupConfig>,
+    /// The L2 execution client provider, used to compute output roots.
+    l2_provider: ReqwestProvider,
+    /// The L2 chain provider, used to fetch L2 block references.
+    l2_chain_provider: AlloyL2ChainProvider,
+    /// The sync status published by the driver.
+    sync_status: watch::Receiver<SyncStatus>,
+    /// The connection state of the L1 subscription, if the node maintains one.
+    l1_connection: Option<Arc<AtomicBool>>,
+}
+
+impl RollupNodeRpc {
+    /// Creates a new [RollupNodeRpc] backed by the L2 execution client at the given [Url].
+    pub fn new_http(
+        cfg: Arc<RollupConfig>,
+        l2_rpc_url: Url,
+        sync_status: watch::Receiver<SyncStatus>,
+    ) -> Self {
+        let l2_provider = ReqwestProvider::new_http(l2_rpc_url.clone());
+        let l2_chain_provider = AlloyL2ChainProvider::new_http(l2_rpc_url, cfg.clone());
+        Self { cfg, l2_provider, l2_chain_provider, sync_status, l1_connection: None }
+    }
+
+    /// Reports the given connection state of the L1 subscription.
+    pub fn with_l1_connection(mut self, l1_connection: Arc<AtomicBool>) -> Self {
+        self.l1_connection = Some(l1_connection);
+        self
+    }
+
+    /// Starts the JSON-RPC server on the given address.
+    pub async fn start(self, addr: SocketAddr) -> Result<ServerHandle> {
+        let server = Server::builder().build(addr).await?;
+        info!("Rollup RPC server listening on {}", server.local_addr()?);
+        Ok(server.start(self.into_rpc()))
+    }
+}
+
+#[async_trait]
+impl RollupNodeApiServer for RollupNodeRpc {
+    async fn sync_status(&self) -> RpcResult<SyncStatus> {
+        Ok(self.sync_status.borrow().clone())
+    }
+
+    async fn rollup_config(&self) -> RpcResult<RollupConfig> {
+        Ok(self.cfg.as_ref().clone())
+    }
+
+    async fn output_at_block(&self, block_number: U64) -> RpcResult<OutputResponse> {
+        let number = block_number.to::<u64>();
+        let output = OutputRoot::fetch(&self.l2_provider, number).await.map_err(internal_error)?;
+        let block = self
+            .l2_chain_provider
+            .clone()
+            .l2_block_info_by_number(number)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(OutputResponse {
+            version: OUTPUT_ROOT_VERSION,
+            output_root: output.hash(),
+            block_ref: l2_block_ref(&block),
+            withdrawal_storage_root: output.withdrawal_storage_root,
+            state_root: output.state_root,
+            sync_status: self.sync_status.borrow().clone(),
+        })
+    }
+
+    async fn version(&self) -> RpcResult<String> {
+        Ok(format!("hera/v{}", env!("CARGO_PKG_VERSION")))
+    }
+
+    async fn l1_connected(&self) -> RpcResult<bool> {
+        Ok(self.l1_connection.as_ref().map_or(true, |c| c.load(Ordering::Relaxed)))
+    }
+}
+
+/// Converts an L1 [BlockInfo] into an [L1BlockRef].
+pub(crate) const fn l1_block_ref(block: &BlockInfo) -> L1BlockRef {
+    L1BlockRef {
+        hash: block.hash,
+        number: block.number,
+        parent_hash: block.parent_hash,
+        timestamp: block.timestamp,
+    }
+}
+
+/// Converts an [L2BlockInfo] into an [L2BlockRef].
+pub(crate) const fn l2_block_ref(block: &L2BlockInfo) -> L2BlockRef {
+    L2BlockRef {
+        hash: block.block_info.hash,
+        number: block.block_info.number,
+        parent_hash: block.block_info.parent_hash,
+        timestamp: block.block_info.timestamp,
+        l1origin: BlockNumHash { number: block.l1_origin.number, hash: block.l1_origin.hash },
+        sequence_number: block.seq_num,
+    }
+}
+
+/// Returns the sync status of a node that has not processed any block yet.
+pub(crate) fn empty_sync_status() -> SyncStatus {
+    let l1 = l1_block_ref(&BlockInfo::default());
+    let l2 = l2_block_ref(&L2BlockInfo::default());
+    SyncStatus {
+        current_l1: l1,
+        current_l1_finalized: l1,
+        head_l1: l1,
+        safe_l1: l1,
+        finalized_l1: l1,
+        unsafe_l2: l2,
+        safe_l2: l2,
+        finalized_l2: l2,
+        pending_safe_l2: l2,
+    }
+}
+
+/// Converts an error into an internal JSON-RPC error.
+fn internal_error(err: impl Display) -> ErrorObjectOwned {
+    ErrorObject::owned(INTERNAL_ERROR_CODE, err.to_string(), None::<()>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rpc() -> (watch::Sender<SyncStatus>, RollupNodeRpc) {
+        let (tx, rx) = watch::channel(empty_sync_status());
+        let cfg = Arc::new(RollupConfig { l2_chain_id: 10, ..Default::default() });
+        let url = Url::parse("http://localhost:8545").unwrap();
+        (tx, RollupNodeRpc::new_http(cfg, url, rx))
+    }
+
+    #[test]
+    fn test_optimism_namespace() {
+        let (_, rpc) = rpc();
+        let module = rpc.into_rpc();
+        let methods = module.method_names().collect::<Vec<_>>();
+
+        for method in [
+            "optimism_syncStatus",
+            "optimism_rollupConfig",
+            "optimism_outputAtBlock",
+            "optimism_version",
+            "optimism_l1Connected",
+        ] {
+            assert!(methods.contains(&method), "missing method {}", method);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_status_follows_driver() {
+        let (tx, rpc) = rpc();
+        assert_eq!(rpc.sync_status().await.unwrap(), empty_sync_status());
+
+        let mut safe = L2BlockInfo::default();
+        safe.block_info.number = 42;
+        safe.l1_origin.number = 7;
+        tx.send_modify(|status| status.safe_l2 = l2_block_ref(&safe));
+
+        let status = rpc.sync_status().await.unwrap();
+        assert_eq!(status.safe_l2.number, 42);
+        assert_eq!(status.safe_l2.l1origin.number, 7);
+    }
+
+    #[tokio::test]
+    async fn test_rollup_config_and_version() {
+        let (_, rpc) = rpc();
+        assert_eq!(rpc.rollup_config().await.unwrap().l2_chain_id, 10);
+        assert!(rpc.version().await.unwrap().starts_with("hera/v"));
+    }
+
+    #[tokio::test]
+    async fn test_l1_connected() {
+        let (_, rpc) = rpc();
+        assert!(rpc.l1_connected().await.unwrap());
+
+        let connected = Arc::new(AtomicBool::new(false));
+        let rpc = rpc.with_l1_connection(connected.clone());
+        assert!(!rpc.l1_connected().await.unwrap());
+        connected.store(true, Ordering::Relaxed);
+        assert!(rpc.l1_connected().await.unwrap());
+    }
+}
@@ -9,8 +9,10 @@ use alloy::{
     rpc::types::engine::PayloadAttributes,
 };
 use async_trait::async_trait;
-use eyre::{bail, eyre, Result};
+use eyre::{bail, eyre, Result, WrapErr};
+use op_alloy_protocol::L2BlockInfo;
 use op_alloy_rpc_types_engine::{OptimismAttributesWithParent, OptimismPayloadAttributes};
+use op_net::types::envelope::ExecutionPayloadEnvelope;
 use reqwest::{
     header::{AUTHORIZATION, CONTENT_TYPE},
     Client, StatusCode,
@@ -30,6 +32,36 @@ pub trait AttributesValidator: Debug + Send {
     /// Validates the given [`OptimismAttributesWithParent`] and returns true
     /// if the attributes are valid, false otherwise.
     async fn validate(&self, attributes: &OptimismAttributesWithParent) -> Result<bool>;
+
+    /// Validates an unsafe [ExecutionPayloadEnvelope] received from the p2p network
+    /// and returns whether the payload is part of the canonical unsafe chain.
+    ///
+    /// By default, unsafe payloads are not supported and an error is returned.
+    async fn validate_unsafe_payload(
+        &self,
+        _envelope: &ExecutionPayloadEnvelope,
+    ) -> Result<UnsafePayloadStatus> {
+        bail!("Unsafe payload validation is not supported by this validator")
+    }
+
+    /// Notifies the validator that the given L2 block has been finalized.
+    ///
+    /// Validators that drive an execution client can use this to advance its
+    /// finalized head. By default, this is a no-op.
+    async fn finalize(&self, _block: &L2BlockInfo) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The result of the validation of an unsafe payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsafePayloadStatus {
+    /// The payload is part of the canonical unsafe chain.
+    Valid,
+    /// The payload is invalid and must be dropped.
+    Invalid,
+    /// The payload could not be validated yet, e.g. while the execution client is syncing.
+    Unknown,
 }
 
 /// TrustedValidator
@@ -62,13 +94,21 @@ impl TrustedValidator {
     /// This method needs to fetch the non-hydrated block and then
     /// fetch the raw transactions using the `debug_*` namespace.
     pub async fn get_block(&self, tag: BlockNumberOrTag) -> Result<(Header, Vec<Bytes>)> {
+        self.find_block(tag).await?.ok_or(eyre!("Block not found"))
+    }
+
+    /// Fetches a block [Header] and a list of raw RLP encoded transactions from the L2 provider,
+    /// or returns `None` if the block does not exist.
+    pub async fn find_block(&self, tag: BlockNumberOrTag) -> Result<Option<(Header, Vec<Bytes>)>> {
         // Don't hydrate the block so we only get a list of transaction hashes.
-        let block = self
+        let Some(block) = self
             .provider
             .get_block(tag.into(), BlockTransactionsKind::Hashes)
             .await
-            .map_err(|e| eyre!(format!("Failed to fetch block: {:?}", e)))?
-            .ok_or(eyre!("Block not found"))?;
+            .wrap_err("Failed to fetch block")?
+        else {
+            return Ok(None);
+        };
 
         // For each transaction hash, fetch the raw transaction RLP.
         let mut txs = vec![];
@@ -77,7 +117,7 @@ impl TrustedValidator {
                 Ok(tx) => txs.push(tx),
                 Err(err) => {
                     error!(?err, "Failed to fetch RLP transaction");
-                    bail!("Failed to fetch transaction");
+                    return Err(err).wrap_err("Failed to fetch transaction");
                 }
             }
         }
@@ -87,14 +127,22 @@ impl TrustedValidator {
             bail!("Transaction count mismatch");
         }
 
-        Ok((block.header, txs))
+        Ok(Some((block.header, txs)))
     }
 
     /// Gets the payload for the specified [BlockNumberOrTag].
     pub async fn get_payload(&self, tag: BlockNumberOrTag) -> Result<OptimismPayloadAttributes> {
         let (header, transactions) = self.get_block(tag).await?;
+        Ok(self.payload_from_block(&header, transactions))
+    }
 
-        Ok(OptimismPayloadAttributes {
+    /// Builds the payload attributes the given block was built from.
+    pub fn payload_from_block(
+        &self,
+        header: &Header,
+        transactions: Vec<Bytes>,
+    ) -> OptimismPayloadAttributes {
+        OptimismPayloadAttributes {
             payload_attributes: PayloadAttributes {
                 timestamp: header.timestamp,
                 suggested_fee_recipient: header.miner,
@@ -106,7 +154,7 @@ impl TrustedValidator {
             transactions: Some(transactions),
             no_tx_pool: Some(true),
             gas_limit: Some(header.gas_limit as u64),
-        })
+        }
     }
 }
 
@@ -120,16 +168,49 @@ impl AttributesValidator for TrustedValidator {
             Ok(payload) => Ok(attributes.attributes == payload),
             Err(err) => {
                 error!(?err, "Failed to fetch payload for block {}", expected);
-                bail!("Failed to fetch payload for block {}: {:?}", expected, err);
+                Err(err.wrap_err(format!("Failed to fetch payload for block {}", expected)))
             }
         }
     }
+
+    async fn validate_unsafe_payload(
+        &self,
+        envelope: &ExecutionPayloadEnvelope,
+    ) -> Result<UnsafePayloadStatus> {
+        canonical_payload_status(&self.provider, envelope).await
+    }
+}
+
+/// Compares the given [ExecutionPayloadEnvelope] with the L2 block with the same number
+/// in the provider's canonical chain.
+///
+/// The payload is unknown if the provider does not have the block yet.
+async fn canonical_payload_status(
+    provider: &ReqwestProvider,
+    envelope: &ExecutionPayloadEnvelope,
+) -> Result<UnsafePayloadStatus> {
+    let number = envelope.payload.block_number;
+    let Some(block) = provider
+        .get_block(number.into(), BlockTransactionsKind::Hashes)
+        .await
+        .wrap_err("Failed to fetch block")?
+    else {
+        return Ok(UnsafePayloadStatus::Unknown);
+    };
+
+    if block.header.hash == envelope.payload.block_hash {
+        Ok(UnsafePayloadStatus::Valid)
+    } else {
+        Ok(UnsafePayloadStatus::Invalid)
+    }
 }
 
 /// EngineApiValidator
 ///
 /// Validates the [`OptimismAttributesWithParent`] by sending the attributes to an L2 engine API.
 /// The engine API will return a `VALID` or `INVALID` response.
+///
+/// Unsafe payloads are validated against the canonical chain of the L2 RPC.
 #[derive(Debug, Clone)]
 pub struct EngineApiValidator {
     /// The engine API URL.
@@ -138,13 +219,17 @@ pub struct EngineApiValidator {
     client: Client,
     /// The JWT secret token for the engine API.
     jwt_secret: JwtSecret,
+    /// The L2 provider.
+    provider: ReqwestProvider,
 }
 
 impl EngineApiValidator {
-    /// Creates a new [`EngineApiValidator`] from the provided [Url] and [JwtSecret].
+    /// Creates a new [`EngineApiValidator`] from the provided engine API [Url], [JwtSecret]
+    /// and L2 RPC [Url].
     #[allow(unused)]
-    pub fn new_http(url: Url, jwt: JwtSecret) -> Self {
-        Self { url, client: Client::new(), jwt_secret: jwt }
+    pub fn new_http(url: Url, jwt: JwtSecret, l2_rpc_url: Url) -> Self {
+        let provider = ReqwestProvider::new_http(l2_rpc_url);
+        Self { url, client: Client::new(), jwt_secret: jwt, provider }
     }
 }
 
@@ -171,6 +256,11 @@ impl AttributesValidator for EngineApiValidator {
             .await?;
 
         let status = response.status();
+        if let Err(err) = response.error_for_status_ref() {
+            error!("Engine API returned status: {}", status);
+            return Err(err).wrap_err("Engine API request failed");
+        }
+
         let body = response.json::<serde_json::Value>().await?;
         match status {
             StatusCode::OK => Ok(body
@@ -183,4 +273,11 @@ impl AttributesValidator for EngineApiValidator {
             }
         }
     }
+
+    async fn validate_unsafe_payload(
+        &self,
+        envelope: &ExecutionPayloadEnvelope,
+    ) -> Result<UnsafePayloadStatus> {
+        canonical_payload_status(&self.provider, envelope).await
+    }
 }